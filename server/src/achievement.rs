@@ -0,0 +1,168 @@
+//! # Achievements
+//! Achievements are declared as rows in the `Achievement` table, each with a [criteria](Criteria)
+//! describing what an adventurer has to do to earn it. This module turns those criteria
+//! into SQL which awards the achievement to everyone who has met them.
+//!
+//! Awarding is idempotent, so it's always safe to [evaluate](evaluate) achievements again.
+//! We do so for the party members of a quest whenever it's completed,
+//! and for everyone when running the `evaluate-achievements` subcommand,
+//! which is how achievements added after the fact get awarded.
+
+use crate::{AchievementId, GuildId, UserId};
+use rusqlite::{named_params, Transaction};
+use serde::{Deserialize, Serialize};
+
+/// What an adventurer has to do to earn an achievement.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Criteria {
+    /// Complete at least `count` quest actions, in a particular guild if one is given.
+    CompleteQuestActions {
+        count: u32,
+        guild_id: Option<GuildId>,
+    },
+    /// Complete a quest action in every guild which hasn't been archived.
+    CompleteQuestActionInEachGuild,
+    /// Complete a single quest action worth at least `xp` XP, in a particular guild if one is given.
+    CompleteQuestActionWorth {
+        xp: u32,
+        guild_id: Option<GuildId>,
+    },
+    /// Earn at least `xp` XP in total, in a particular guild if one is given.
+    EarnXp {
+        xp: u32,
+        guild_id: Option<GuildId>,
+    },
+}
+
+/// The columns of the `Achievement` table which store a [`Criteria`]:
+/// `criteria_type`, `guild_id`, `count`, and `xp`.
+pub(crate) type CriteriaColumns = (i64, Option<GuildId>, Option<u32>, Option<u32>);
+
+impl Criteria {
+    pub(crate) fn guild_id(self) -> Option<GuildId> {
+        match self {
+            Self::CompleteQuestActions { guild_id, .. }
+            | Self::CompleteQuestActionWorth { guild_id, .. }
+            | Self::EarnXp { guild_id, .. } => guild_id,
+            Self::CompleteQuestActionInEachGuild => None,
+        }
+    }
+
+    pub(crate) fn to_columns(self) -> CriteriaColumns {
+        match self {
+            Self::CompleteQuestActions { count, guild_id } => (0, guild_id, Some(count), None),
+            Self::CompleteQuestActionInEachGuild => (1, None, None, None),
+            Self::CompleteQuestActionWorth { xp, guild_id } => (2, guild_id, None, Some(xp)),
+            Self::EarnXp { xp, guild_id } => (3, guild_id, None, Some(xp)),
+        }
+    }
+
+    /// Returns `None` if the columns don't describe a criteria we know about.
+    pub(crate) fn from_columns((criteria_type, guild_id, count, xp): CriteriaColumns) -> Option<Self> {
+        match criteria_type {
+            0 => Some(Self::CompleteQuestActions { count: count?, guild_id }),
+            1 => Some(Self::CompleteQuestActionInEachGuild),
+            2 => Some(Self::CompleteQuestActionWorth { xp: xp?, guild_id }),
+            3 => Some(Self::EarnXp { xp: xp?, guild_id }),
+            _ => None,
+        }
+    }
+
+    /// The SQL which awards an achievement with this criteria.
+    ///
+    /// Each statement takes some of these parameters: `:achievement_id`, `:guild_id`, `:count`, `:xp`,
+    /// and `:adventurer_id`, which limits the evaluation to one adventurer if it isn't `NULL`.
+    fn award_sql(self) -> &'static str {
+        match self {
+            Self::CompleteQuestActions { .. } => {
+                "INSERT INTO AdventurerAchievement (adventurer_id, achievement_id, award_date)
+                     SELECT PartyMember.adventurer_id, :achievement_id, unixepoch()
+                     FROM PartyMember
+                         INNER JOIN Quest ON Quest.id = PartyMember.quest_id
+                     WHERE Quest.quest_type = 1 AND Quest.close_date IS NOT NULL AND Quest.deleted_date IS NULL
+                         AND (:guild_id IS NULL OR Quest.guild_id = :guild_id)
+                         AND (:adventurer_id IS NULL OR PartyMember.adventurer_id = :adventurer_id)
+                     GROUP BY PartyMember.adventurer_id
+                     HAVING COUNT(DISTINCT Quest.id) >= :count
+                     ON CONFLICT DO NOTHING;"
+            }
+            Self::CompleteQuestActionInEachGuild => {
+                "INSERT INTO AdventurerAchievement (adventurer_id, achievement_id, award_date)
+                     SELECT PartyMember.adventurer_id, :achievement_id, unixepoch()
+                     FROM PartyMember
+                         INNER JOIN Quest ON Quest.id = PartyMember.quest_id
+                     WHERE Quest.quest_type = 1 AND Quest.close_date IS NOT NULL AND Quest.deleted_date IS NULL
+                         AND Quest.guild_id IN (SELECT id FROM Guild WHERE archived_date IS NULL)
+                         AND (:adventurer_id IS NULL OR PartyMember.adventurer_id = :adventurer_id)
+                     GROUP BY PartyMember.adventurer_id
+                     HAVING COUNT(DISTINCT Quest.guild_id) >= (SELECT COUNT(*) FROM Guild WHERE archived_date IS NULL)
+                     ON CONFLICT DO NOTHING;"
+            }
+            Self::CompleteQuestActionWorth { .. } => {
+                "INSERT INTO AdventurerAchievement (adventurer_id, achievement_id, award_date)
+                     SELECT PartyMember.adventurer_id, :achievement_id, unixepoch()
+                     FROM PartyMember
+                         INNER JOIN Quest ON Quest.id = PartyMember.quest_id
+                     WHERE Quest.quest_type = 1 AND Quest.close_date IS NOT NULL AND Quest.deleted_date IS NULL
+                         AND (:guild_id IS NULL OR Quest.guild_id = :guild_id)
+                         AND (:adventurer_id IS NULL OR PartyMember.adventurer_id = :adventurer_id)
+                         AND (SELECT SUM(xp) FROM QuestTask WHERE QuestTask.quest_id = Quest.id) >= :xp
+                     GROUP BY PartyMember.adventurer_id
+                     ON CONFLICT DO NOTHING;"
+            }
+            Self::EarnXp { .. } => {
+                "INSERT INTO AdventurerAchievement (adventurer_id, achievement_id, award_date)
+                     SELECT adventurer_id, :achievement_id, unixepoch()
+                     FROM XpAward
+                     WHERE (:guild_id IS NULL OR guild_id = :guild_id)
+                         AND (:adventurer_id IS NULL OR adventurer_id = :adventurer_id)
+                     GROUP BY adventurer_id
+                     HAVING SUM(xp) >= :xp
+                     ON CONFLICT DO NOTHING;"
+            }
+        }
+    }
+}
+
+/// Award every achievement to everyone who has met its criteria but doesn't have it yet,
+/// or just to one adventurer, if one is given.
+///
+/// Returns the number of achievements newly awarded.
+pub(crate) fn evaluate(db: &Transaction, adventurer: Option<UserId>) -> Result<usize, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT id, criteria_type, guild_id, count, xp FROM Achievement;",
+    )?;
+    let achievements = query
+        .query_map([], |row| {
+            let id: AchievementId = row.get(0)?;
+            let columns = (row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?);
+            Ok((id, columns))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut awarded = 0;
+    for (id, columns) in achievements {
+        let Some(criteria) = Criteria::from_columns(columns) else {
+            tracing::warn!("skipping achievement {id}, which has unknown criteria: {columns:?}");
+            continue;
+        };
+        let (_, guild_id, count, xp) = columns;
+        let mut award = db.prepare_cached(criteria.award_sql())?;
+        // Not every statement uses every parameter, and binding one which isn't used is an error.
+        let params = named_params! {
+            ":achievement_id": id,
+            ":guild_id": guild_id,
+            ":count": count,
+            ":xp": xp,
+            ":adventurer_id": adventurer,
+        };
+        for (name, value) in params {
+            if let Some(index) = award.parameter_index(name)? {
+                award.raw_bind_parameter(index, value)?;
+            }
+        }
+        awarded += award.raw_execute()?;
+    }
+    Ok(awarded)
+}
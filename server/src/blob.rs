@@ -0,0 +1,73 @@
+//! # Blob Storage
+//! This module provides storage for uploaded files, which we'd rather not keep in the database.
+//! Every blob is addressed by a key which we generate when storing it,
+//! and which the database records alongside whatever the blob is attached to.
+
+use rand::Rng;
+use std::io;
+use std::path::PathBuf;
+
+/// A place to keep blobs.
+///
+/// Only [`LocalBlobStore`] exists right now, but this is where something
+/// like an S3 bucket would plug in.
+pub(crate) trait BlobStore: Send + Sync {
+    /// Save a blob under the given key, replacing any blob already stored there.
+    fn put(&self, key: &str, data: &[u8]) -> io::Result<()>;
+    /// Read back the blob stored under the given key.
+    fn get(&self, key: &str) -> io::Result<Vec<u8>>;
+    /// Remove the blob stored under the given key.
+    /// Removing a blob which doesn't exist is not an error.
+    fn delete(&self, key: &str) -> io::Result<()>;
+}
+
+/// Generate a new, random blob key.
+///
+/// Keys are 32 lowercase hex digits, so they're safe to use as file names.
+pub(crate) fn generate_key() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// A [`BlobStore`] which keeps each blob as a file in a directory on local disk.
+pub(crate) struct LocalBlobStore {
+    root: PathBuf,
+}
+impl LocalBlobStore {
+    /// Open a blob store in the given directory, creating the directory if needed.
+    pub(crate) fn new(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        // Keys come out of our own database, but we still don't want a bad one
+        // to let anybody wander outside of the blob directory.
+        if key.is_empty() || !key.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid blob key: {key:?}")));
+        }
+        Ok(self.root.join(key))
+    }
+}
+impl BlobStore for LocalBlobStore {
+    fn put(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.path(key)?;
+        // Write to a temporary file first, so a partially written blob
+        // is never visible under its real key.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, &path)
+    }
+
+    fn get(&self, key: &str) -> io::Result<Vec<u8>> {
+        std::fs::read(self.path(key)?)
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        match std::fs::remove_file(self.path(key)?) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! # Quest Action Catalogues
+//! A guild's catalogue is the list of its quest actions, written out as CSV or JSON,
+//! so that it can be backed up, or moved between instances of the app.
+//!
+//! Both the API and the `import-quest-actions` / `export-quest-actions` subcommands use this.
+//! Prerequisites aren't included, since they refer to quest actions by ID,
+//! and IDs don't carry over between instances.
+
+use crate::{db, normalize_tags, validate_completion_days, CreateGuildQuestAction, Error, GuildId, JsTimestamp, QuestId, QuestSchedule, RepeatLimits, MAX_COMPLETION_DAYS, MAX_TAGS};
+use rusqlite::Transaction;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CatalogueFormat {
+    Csv,
+    #[default]
+    Json,
+}
+
+impl CatalogueFormat {
+    pub(crate) fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Json => "application/json",
+        }
+    }
+
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+impl core::str::FromStr for CatalogueFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown catalogue format {s:?}, expected \"csv\" or \"json\"")),
+        }
+    }
+}
+
+/// A quest action in a JSON catalogue.
+#[derive(Serialize, Deserialize, Debug)]
+struct CatalogueEntry {
+    #[serde(flatten)]
+    action: CreateGuildQuestAction,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// A quest action in a CSV catalogue.
+///
+/// CSV can't nest, so this spells out every field of [`CatalogueEntry`],
+/// and the tags are written in one column, separated by commas.
+#[derive(Serialize, Deserialize, Debug)]
+struct CsvEntry {
+    // The headers match the field names of quest actions in the JSON API, where these two are swapped.
+    #[serde(rename = "description")]
+    name: String,
+    #[serde(rename = "name")]
+    description: Option<String>,
+    adventurer_note: Option<String>,
+    xp: u32,
+    #[serde(default)]
+    repeatable: bool,
+    repeat_cooldown: Option<u32>,
+    repeat_limit: Option<u32>,
+    repeat_period: Option<u32>,
+    open_date: Option<JsTimestamp>,
+    close_date: Option<JsTimestamp>,
+    completion_days: Option<u32>,
+    #[serde(default)]
+    members_only: bool,
+    #[serde(default)]
+    tags: String,
+}
+
+impl From<CsvEntry> for CatalogueEntry {
+    fn from(entry: CsvEntry) -> Self {
+        Self {
+            action: CreateGuildQuestAction {
+                name: entry.name,
+                description: entry.description,
+                adventurer_note: entry.adventurer_note,
+                xp: entry.xp,
+                repeatable: entry.repeatable,
+                repeat_limits: RepeatLimits {
+                    repeat_cooldown: entry.repeat_cooldown,
+                    repeat_limit: entry.repeat_limit,
+                    repeat_period: entry.repeat_period,
+                },
+                schedule: QuestSchedule { open_date: entry.open_date, close_date: entry.close_date },
+                completion_days: entry.completion_days,
+                members_only: entry.members_only,
+            },
+            tags: entry.tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(String::from).collect(),
+        }
+    }
+}
+
+impl From<CatalogueEntry> for CsvEntry {
+    fn from(CatalogueEntry { action, tags }: CatalogueEntry) -> Self {
+        Self {
+            name: action.name,
+            description: action.description,
+            adventurer_note: action.adventurer_note,
+            xp: action.xp,
+            repeatable: action.repeatable,
+            repeat_cooldown: action.repeat_limits.repeat_cooldown,
+            repeat_limit: action.repeat_limits.repeat_limit,
+            repeat_period: action.repeat_limits.repeat_period,
+            open_date: action.schedule.open_date,
+            close_date: action.schedule.close_date,
+            completion_days: action.completion_days,
+            members_only: action.members_only,
+            tags: tags.join(", "),
+        }
+    }
+}
+
+/// A problem with one of the quest actions in a catalogue.
+#[derive(Serialize, Debug)]
+pub(crate) struct RowError {
+    /// For CSV, the line the quest action is on.
+    /// For JSON, its position in the array, counting from 1.
+    pub(crate) row: u64,
+    pub(crate) message: String,
+}
+
+/// What happened, or would happen, when importing a catalogue.
+#[derive(Serialize, Debug)]
+pub(crate) struct ImportReport {
+    pub(crate) dry_run: bool,
+    /// How many quest actions in the catalogue are valid.
+    pub(crate) valid: usize,
+    /// The quest actions which were created.
+    /// This is empty for a dry run, or if there were any errors.
+    pub(crate) imported: Vec<QuestId>,
+    pub(crate) errors: Vec<RowError>,
+}
+
+/// A quest action read from a catalogue, or what's wrong with it, along with where it is in the catalogue.
+type ParsedEntry = (u64, Result<CatalogueEntry, String>);
+
+/// Read every quest action from a catalogue, keeping going after bad rows so they can all be reported.
+///
+/// Fails outright only when the catalogue as a whole can't be read,
+/// like when a JSON catalogue isn't an array.
+fn parse(format: CatalogueFormat, data: &[u8]) -> Result<Vec<ParsedEntry>, String> {
+    match format {
+        CatalogueFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(data);
+            let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+            let mut entries = Vec::new();
+            for record in reader.records() {
+                let record = match record {
+                    Ok(record) => record,
+                    Err(e) => {
+                        let row = e.position().map_or(0, |position| position.line());
+                        entries.push((row, Err(e.to_string())));
+                        continue;
+                    }
+                };
+                let row = record.position().map_or(0, |position| position.line());
+                let entry = record
+                    .deserialize::<CsvEntry>(Some(&headers))
+                    .map(CatalogueEntry::from)
+                    .map_err(|e| match e.kind() {
+                        csv::ErrorKind::Deserialize { err, .. } => match err.field().and_then(|i| headers.get(i as usize)) {
+                            Some(column) => format!("{column}: {}", err.kind()),
+                            None => err.to_string(),
+                        },
+                        _ => e.to_string(),
+                    });
+                entries.push((row, entry));
+            }
+            Ok(entries)
+        }
+        CatalogueFormat::Json => {
+            let values: Vec<serde_json::Value> =
+                serde_json::from_slice(data).map_err(|e| format!("expected an array of quest actions: {e}"))?;
+            let entries = values
+                .into_iter()
+                .zip(1..)
+                .map(|(value, row)| (row, serde_json::from_value(value).map_err(|e| e.to_string())))
+                .collect();
+            Ok(entries)
+        }
+    }
+}
+
+/// Check a quest action from a catalogue, normalizing its tags.
+fn validate(entry: &mut CatalogueEntry) -> Result<(), String> {
+    if entry.action.name.trim().is_empty() {
+        return Err("a quest action's description must not be empty".to_string());
+    }
+    if entry.action.schedule.validate().is_err() {
+        return Err("a quest action's close date must be after its open date".to_string());
+    }
+    if entry.action.repeat_limits.validate().is_err() {
+        return Err("a quest action's repeat limit and repeat period must be at least 1".to_string());
+    }
+    if validate_completion_days(entry.action.completion_days).is_err() {
+        return Err(format!("a quest action must give adventurers between 1 and {MAX_COMPLETION_DAYS} days to complete it"));
+    }
+    entry.tags = match normalize_tags(&entry.tags) {
+        Ok(tags) => tags,
+        Err(Error::InvalidTag { tag }) => return Err(format!("invalid tag {tag:?}")),
+        Err(e) => return Err(format!("invalid tags: {e:?}")),
+    };
+    if entry.tags.len() > MAX_TAGS {
+        return Err(format!("a quest action may have at most {MAX_TAGS} tags"));
+    }
+    Ok(())
+}
+
+/// Add every quest action in a catalogue to a guild.
+///
+/// If any of them are invalid, none of them are added, and the report says what's wrong with each.
+/// A dry run only checks the catalogue.
+pub(crate) fn import(
+    db: &Transaction,
+    guild: GuildId,
+    format: CatalogueFormat,
+    data: &[u8],
+    dry_run: bool,
+) -> Result<ImportReport, Error> {
+    if !db::guild_exists(db, guild)? {
+        return Err(Error::GuildNotFound { id: Some(guild) });
+    }
+    let entries = parse(format, data).map_err(|msg| Error::InvalidCatalogue { msg })?;
+
+    let mut valid = Vec::with_capacity(entries.len());
+    let mut errors = Vec::new();
+    for (row, entry) in entries {
+        match entry.and_then(|mut entry| validate(&mut entry).map(|()| entry)) {
+            Ok(entry) => valid.push(entry),
+            Err(message) => errors.push(RowError { row, message }),
+        }
+    }
+
+    let mut imported = Vec::new();
+    if !dry_run && errors.is_empty() {
+        for entry in &valid {
+            let quest_id = db::create_guild_quest_action(db, guild, &entry.action)?;
+            db::set_quest_tags(db, quest_id, &entry.tags)?;
+            imported.push(quest_id);
+        }
+    }
+
+    Ok(ImportReport { dry_run, valid: valid.len(), imported, errors })
+}
+
+/// Write out every quest action of a guild which hasn't been retired.
+pub(crate) fn export(db: &Transaction, guild: GuildId, format: CatalogueFormat) -> Result<Vec<u8>, Error> {
+    let Some(quest_actions) = db::lookup_guild_quest_actions(db, guild)? else {
+        return Err(Error::GuildNotFound { id: Some(guild) });
+    };
+    let entries = quest_actions.into_iter().map(|quest_action| CatalogueEntry {
+        action: CreateGuildQuestAction {
+            name: quest_action.name,
+            description: quest_action.description,
+            adventurer_note: quest_action.adventurer_note,
+            xp: quest_action.xp,
+            repeatable: quest_action.repeatable,
+            repeat_limits: quest_action.repeat_limits,
+            schedule: quest_action.schedule,
+            completion_days: quest_action.completion_days,
+            members_only: quest_action.members_only,
+        },
+        tags: quest_action.tags,
+    });
+
+    match format {
+        CatalogueFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for entry in entries {
+                writer.serialize(CsvEntry::from(entry)).expect("writing CSV to memory can't fail");
+            }
+            Ok(writer.into_inner().expect("writing CSV to memory can't fail"))
+        }
+        CatalogueFormat::Json => {
+            let entries = entries.collect::<Vec<_>>();
+            Ok(serde_json::to_vec_pretty(&entries).expect("quest actions are always valid JSON"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(json: &str) -> CatalogueEntry {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn malformed_csv_rows_are_reported_without_stopping_the_import() {
+        let csv = "description,xp,tags\n\
+                   Read the handbook,10,reading\n\
+                   Too,many,columns,here\n\
+                   Write a blog post,lots,\n\
+                   Mentor someone,20,\"mentoring, hiring\"\n";
+        let entries = parse(CatalogueFormat::Csv, csv.as_bytes()).unwrap();
+        let rows: Vec<_> = entries.iter().map(|(row, entry)| (*row, entry.is_ok())).collect();
+        assert_eq!(rows, [(2, true), (3, false), (4, false), (5, true)]);
+        let Ok(mentor) = &entries[3].1 else { unreachable!() };
+        assert_eq!(mentor.tags, ["mentoring", "hiring"]);
+        let Err(message) = &entries[2].1 else { unreachable!() };
+        assert!(message.starts_with("xp:"), "{message}");
+    }
+
+    #[test]
+    fn bad_json_entries_are_reported_by_position() {
+        let json = r#"[{"description": "Read the handbook", "xp": 10}, {"description": "No XP"}, 7]"#;
+        let entries = parse(CatalogueFormat::Json, json.as_bytes()).unwrap();
+        let rows: Vec<_> = entries.iter().map(|(row, entry)| (*row, entry.is_ok())).collect();
+        assert_eq!(rows, [(1, true), (2, false), (3, false)]);
+        assert!(parse(CatalogueFormat::Json, b"{}").is_err());
+    }
+
+    #[test]
+    fn validation() {
+        let mut valid = entry(r#"{"description": "Read the handbook", "xp": 10, "tags": [" Reading "]}"#);
+        assert_eq!(validate(&mut valid), Ok(()));
+        assert_eq!(valid.tags, ["reading"]);
+
+        for (json, expected) in [
+            (r#"{"description": "  ", "xp": 10}"#, "must not be empty"),
+            (r#"{"description": "Read", "xp": 10, "repeat_limit": 0}"#, "repeat limit"),
+            (r#"{"description": "Read", "xp": 10, "repeat_limit": 1, "repeat_period": 0}"#, "repeat period"),
+            (r#"{"description": "Read", "xp": 10, "completion_days": 0}"#, "between 1 and"),
+            (r#"{"description": "Read", "xp": 10, "completion_days": 3651}"#, "between 1 and"),
+            (r#"{"description": "Read", "xp": 10, "tags": ["no/slashes"]}"#, "invalid tag"),
+        ] {
+            let message = validate(&mut entry(json)).unwrap_err();
+            assert!(message.contains(expected), "{json}: {message}");
+        }
+
+        let mut too_many_tags = entry(r#"{"description": "Read", "xp": 10}"#);
+        too_many_tags.tags = (0..=MAX_TAGS).map(|i| format!("tag{i}")).collect();
+        assert!(validate(&mut too_many_tags).unwrap_err().contains("at most"));
+    }
+}
@@ -0,0 +1,19 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use argh::FromArgs;
+use crate::{achievement, AppState};
+
+/// Award every achievement to every adventurer who has met its criteria.
+/// Run this after adding achievements, to award them to those who earned them already.
+#[derive(FromArgs)]
+#[argh(subcommand, name = "evaluate-achievements")]
+pub struct EvaluateAchievements {}
+
+pub fn evaluate_achievements(state: Arc<AppState>, EvaluateAchievements {}: EvaluateAchievements) {
+    let res = state.write_transaction(|db| {
+        let awarded = achievement::evaluate(db, None)?;
+        Ok::<_, crate::Error<Infallible>>(awarded)
+    });
+
+    println!("Awarded {} achievements", res.unwrap());
+}
@@ -0,0 +1,42 @@
+//! This module provides the `export-quest-actions` subcommand,
+//! which writes out the quest actions of a guild as a catalogue file.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use argh::FromArgs;
+use crate::catalogue::{self, CatalogueFormat};
+use crate::{AppState, GuildId};
+
+/// Export the quest actions of a guild as a CSV or JSON catalogue,
+/// which `import-quest-actions` can read.
+#[derive(FromArgs)]
+#[argh(subcommand, name = "export-quest-actions")]
+pub struct ExportQuestActions {
+    /// the ID of the guild to export the quest actions of
+    #[argh(positional)]
+    guild_id: u32,
+    /// the file to write the catalogue to. If this isn't given, it's written to stdout.
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+    /// the format of the catalogue, "csv" or "json".
+    /// If this isn't given, it's guessed from the output file's extension.
+    #[argh(option)]
+    format: Option<CatalogueFormat>,
+}
+
+pub fn export_quest_actions(state: Arc<AppState>, args: ExportQuestActions) {
+    let format = args.format.unwrap_or_else(|| {
+        let extension = args.output.as_ref().and_then(|path| path.extension()).and_then(|ext| ext.to_str());
+        extension.unwrap_or_default().parse().unwrap_or_default()
+    });
+
+    let data = state
+        .read_transaction(|db| catalogue::export(db, GuildId(args.guild_id), format))
+        .unwrap();
+
+    match args.output {
+        Some(path) => std::fs::write(path, data).unwrap(),
+        None => std::io::stdout().write_all(&data).unwrap(),
+    }
+}
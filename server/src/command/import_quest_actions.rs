@@ -0,0 +1,53 @@
+//! This module provides the `import-quest-actions` subcommand,
+//! which adds the quest actions in a catalogue file to a guild.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use argh::FromArgs;
+use crate::catalogue::{self, CatalogueFormat};
+use crate::{AppState, GuildId};
+
+/// Import quest actions into a guild from a CSV or JSON catalogue,
+/// like one written by `export-quest-actions`.
+#[derive(FromArgs)]
+#[argh(subcommand, name = "import-quest-actions")]
+pub struct ImportQuestActions {
+    /// the ID of the guild to add the quest actions to
+    #[argh(positional)]
+    guild_id: u32,
+    /// the catalogue file to read
+    #[argh(positional)]
+    path: PathBuf,
+    /// the format of the catalogue, "csv" or "json".
+    /// If this isn't given, it's guessed from the file's extension.
+    #[argh(option)]
+    format: Option<CatalogueFormat>,
+    /// check the catalogue for problems, without importing anything
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+pub fn import_quest_actions(state: Arc<AppState>, args: ImportQuestActions) {
+    let format = args.format.unwrap_or_else(|| {
+        let extension = args.path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        extension.parse().unwrap_or_default()
+    });
+    let data = std::fs::read(&args.path).unwrap();
+
+    let report = state
+        .write_transaction(|db| catalogue::import(db, GuildId(args.guild_id), format, &data, args.dry_run))
+        .unwrap();
+
+    for error in &report.errors {
+        eprintln!("row {}: {}", error.row, error.message);
+    }
+    if !report.errors.is_empty() {
+        eprintln!("{} of the quest actions are invalid, so none were imported", report.errors.len());
+        std::process::exit(1);
+    }
+    if report.dry_run {
+        println!("All {} quest actions are valid", report.valid);
+    } else {
+        println!("Imported {} quest actions", report.imported.len());
+    }
+}
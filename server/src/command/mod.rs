@@ -4,12 +4,18 @@
 
 use argh::FromArgs;
 use crate::command::add_admin::AddAdmin;
+use crate::command::evaluate_achievements::EvaluateAchievements;
+use crate::command::export_quest_actions::ExportQuestActions;
 use crate::command::hash_password::HashPassword;
+use crate::command::import_quest_actions::ImportQuestActions;
 use crate::command::insert_demo::InsertDemo;
 
 pub mod hash_password;
 pub mod add_admin;
 pub mod insert_demo;
+pub mod evaluate_achievements;
+pub mod import_quest_actions;
+pub mod export_quest_actions;
 
 /// The DEI adventures API server.
 #[derive(FromArgs)]
@@ -25,6 +31,9 @@ pub enum Subcommand {
     AddAdmin(AddAdmin),
     HashPassword(HashPassword),
     InsertDemo(InsertDemo),
+    EvaluateAchievements(EvaluateAchievements),
+    ImportQuestActions(ImportQuestActions),
+    ExportQuestActions(ExportQuestActions),
 }
 
 /// Run the server process.
@@ -5,8 +5,8 @@
 
 mod migrate;
 
-use crate::{GuildId, GuildQuestAction, Password, PermissionType, QuestId, UserId};
-use rusqlite::{named_params, Transaction};
+use crate::{AttachmentId, AuthToken, Campaign, CampaignId, CommentId, CreateGuildQuestAction, EarnedAchievement, GuildCapability, GuildId, GuildLeader, GuildRole, GuildQuestAction, Kudos, KudosId, LeaderboardEntry, LevelThreshold, Password, PermissionType, QuestComment, QuestId, QuestTaskId, QuestSchedule, QuestTemplate, QuestTemplateId, RedemptionId, RedemptionStatus, RepeatLimits, Reward, RewardId, TermEndReason, UserId};
+use rusqlite::{named_params, OptionalExtension, Transaction};
 use serde::Deserialize;
 
 // A randomly generated number. This is hardcoded elsewhere,
@@ -31,6 +31,12 @@ pub(crate) fn open(path: &std::path::Path) -> Result<rusqlite::Connection, rusql
     Ok(db)
 }
 
+/// Open a fresh database in memory, for tests.
+#[cfg(test)]
+pub(crate) fn open_in_memory() -> rusqlite::Connection {
+    open(std::path::Path::new(":memory:")).unwrap()
+}
+
 pub(crate) fn adventurer_exists(db: &Transaction, user: UserId) -> Result<bool, rusqlite::Error> {
     let mut query = db.prepare_cached("SELECT 0 FROM Adventurer WHERE id = :id")?;
     query.exists(named_params! { ":id": user })
@@ -41,13 +47,548 @@ pub(crate) fn quest_exists(db: &Transaction, quest: QuestId) -> Result<bool, rus
         db.prepare_cached("SELECT 0 FROM Quest WHERE id = :id AND deleted_date IS NULL")?;
     query.exists(named_params! { ":id": quest })
 }
-// TODO: provide deleted_quest_exists() for checking for deleted quests?
+pub(crate) fn guild_quest_exists(db: &Transaction, quest: QuestId) -> Result<bool, rusqlite::Error> {
+    let mut query =
+        db.prepare_cached("SELECT 0 FROM Quest WHERE id = :id AND quest_type = 0 AND deleted_date IS NULL")?;
+    query.exists(named_params! { ":id": quest })
+}
+pub(crate) fn quest_abandoned(db: &Transaction, quest: QuestId) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached("SELECT 0 FROM Quest WHERE id = :id AND abandoned_date IS NOT NULL")?;
+    query.exists(named_params! { ":id": quest })
+}
+pub(crate) fn deleted_quest_exists(db: &Transaction, quest: QuestId) -> Result<bool, rusqlite::Error> {
+    let mut query =
+        db.prepare_cached("SELECT 0 FROM Quest WHERE id = :id AND deleted_date IS NOT NULL")?;
+    query.exists(named_params! { ":id": quest })
+}
 
 pub(crate) fn guild_exists(db: &Transaction, quest: GuildId) -> Result<bool, rusqlite::Error> {
     let mut query = db.prepare_cached("SELECT 0 FROM Guild WHERE id = :id")?;
     query.exists(named_params! { ":id": quest })
 }
 
+pub(crate) fn guild_archived(db: &Transaction, guild: GuildId) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached("SELECT 0 FROM Guild WHERE id = :id AND archived_date IS NOT NULL")?;
+    query.exists(named_params! { ":id": guild })
+}
+
+/// If a quest belongs to a guild which has been archived, that guild.
+pub(crate) fn archived_quest_guild(db: &Transaction, quest: QuestId) -> Result<Option<GuildId>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT Guild.id FROM Quest
+             INNER JOIN Guild ON Guild.id = Quest.guild_id
+             WHERE Quest.id = :quest_id AND Guild.archived_date IS NOT NULL",
+    )?;
+    query.query_row(named_params! { ":quest_id": quest }, |row| row.get(0)).optional()
+}
+
+/// The guild a quest action belongs to, if it exists and hasn't been retired.
+pub(crate) fn quest_action_guild(db: &Transaction, quest: QuestId) -> Result<Option<GuildId>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT guild_id FROM Quest WHERE id = :quest_id AND quest_type = 0 AND deleted_date IS NULL",
+    )?;
+    query.query_row(named_params! { ":quest_id": quest }, |row| row.get(0)).optional()
+}
+
+/// Archive a guild, or bring it back from the archive.
+pub(crate) fn set_guild_archived(db: &Transaction, guild: GuildId, archived: bool) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "UPDATE Guild SET archived_date = CASE WHEN :archived THEN COALESCE(archived_date, unixepoch()) END
+             WHERE id = :guild_id;",
+    )?;
+    query.execute(named_params! { ":guild_id": guild, ":archived": archived })?;
+    Ok(())
+}
+
+/// The kinds of history a guild has, which would be lost if it were deleted.
+/// A guild can only be deleted when this is empty.
+pub(crate) fn guild_history(db: &Transaction, guild: GuildId) -> Result<Vec<&'static str>, rusqlite::Error> {
+    const HISTORY: [(&str, &str); 4] = [
+        ("quests", "SELECT 0 FROM Quest WHERE guild_id = :guild_id"),
+        ("XP awards", "SELECT 0 FROM XpAward WHERE guild_id = :guild_id"),
+        ("achievements", "SELECT 0 FROM Achievement WHERE guild_id = :guild_id"),
+        ("leadership terms", "SELECT 0 FROM GuildLeadershipTerm WHERE guild_id = :guild_id"),
+    ];
+    let mut history = Vec::new();
+    for (kind, sql) in HISTORY {
+        if db.prepare_cached(sql)?.exists(named_params! { ":guild_id": guild })? {
+            history.push(kind);
+        }
+    }
+    Ok(history)
+}
+
+/// Permanently delete a guild which has no history, along with its roles,
+/// members, level thresholds, participation in campaigns, and notifications about it.
+///
+/// Returns the blob key of the guild's icon, if it had one,
+/// so it can be removed from the blob store once the transaction has been committed.
+pub(crate) fn delete_guild(db: &Transaction, guild: GuildId) -> Result<Option<String>, rusqlite::Error> {
+    let icon_blob_key = guild_icon(db, guild)?;
+    for sql in [
+        "DELETE FROM AdventurerRole WHERE guild_id = :guild_id;",
+        "DELETE FROM GuildMember WHERE guild_id = :guild_id;",
+        "DELETE FROM LevelThreshold WHERE guild_id = :guild_id;",
+        "DELETE FROM CampaignGuild WHERE guild_id = :guild_id;",
+        "DELETE FROM Notification WHERE guild_id = :guild_id;",
+        "DELETE FROM Guild WHERE id = :guild_id;",
+    ] {
+        db.prepare_cached(sql)?.execute(named_params! { ":guild_id": guild })?;
+    }
+    Ok(icon_blob_key)
+}
+
+/// The blob key of a guild's icon, if it has one.
+pub(crate) fn guild_icon(db: &Transaction, guild: GuildId) -> Result<Option<String>, rusqlite::Error> {
+    let mut query = db.prepare_cached("SELECT icon_blob_key FROM Guild WHERE id = :guild_id;")?;
+    let key = query.query_row(named_params! { ":guild_id": guild }, |row| row.get(0)).optional()?;
+    Ok(key.flatten())
+}
+
+/// Set or remove a guild's icon, returning the blob key of the icon it had before, if any.
+pub(crate) fn set_guild_icon(db: &Transaction, guild: GuildId, blob_key: Option<&str>) -> Result<Option<String>, rusqlite::Error> {
+    let old_key = guild_icon(db, guild)?;
+    let mut query = db.prepare_cached("UPDATE Guild SET icon_blob_key = :blob_key WHERE id = :guild_id;")?;
+    query.execute(named_params! { ":guild_id": guild, ":blob_key": blob_key })?;
+    Ok(old_key)
+}
+
+/// Whether an adventurer leads at least one guild.
+pub(crate) fn leads_any_guild(db: &Transaction, user: UserId) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT 0 FROM AdventurerRole WHERE adventurer_id = :adventurer_id AND assigned_role = :role",
+    )?;
+    query.exists(named_params! { ":adventurer_id": user, ":role": GuildRole::Leader })
+}
+
+/// The blob key of an adventurer's avatar, if they have one.
+pub(crate) fn adventurer_avatar(db: &Transaction, user: UserId) -> Result<Option<String>, rusqlite::Error> {
+    let mut query = db.prepare_cached("SELECT avatar_blob_key FROM Adventurer WHERE id = :adventurer_id;")?;
+    let key = query.query_row(named_params! { ":adventurer_id": user }, |row| row.get(0)).optional()?;
+    Ok(key.flatten())
+}
+
+/// Set or remove an adventurer's avatar, returning the blob key of the avatar they had before, if any.
+pub(crate) fn set_adventurer_avatar(
+    db: &Transaction,
+    user: UserId,
+    blob_key: Option<&str>,
+) -> Result<Option<String>, rusqlite::Error> {
+    let old_key = adventurer_avatar(db, user)?;
+    let mut query =
+        db.prepare_cached("UPDATE Adventurer SET avatar_blob_key = :blob_key WHERE id = :adventurer_id;")?;
+    query.execute(named_params! { ":adventurer_id": user, ":blob_key": blob_key })?;
+    Ok(old_key)
+}
+
+pub(crate) fn is_guild_member(db: &Transaction, user: UserId, guild: GuildId) -> Result<bool, rusqlite::Error> {
+    let mut query =
+        db.prepare_cached("SELECT 0 FROM GuildMember WHERE guild_id = :guild_id AND adventurer_id = :adventurer_id")?;
+    query.exists(named_params! { ":guild_id": guild, ":adventurer_id": user })
+}
+
+/// Whether an adventurer is allowed to lead guilds.
+/// Super users always are, and anyone else has to have been made eligible.
+pub(crate) fn can_lead_guilds(db: &Transaction, user: UserId) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT 0 FROM Permission
+             WHERE adventurer_id = :adventurer_id AND permission_type IN (:super_user, :guild_leader_eligible);",
+    )?;
+    query.exists(named_params! {
+        ":adventurer_id": user,
+        ":super_user": PermissionType::SuperUser,
+        ":guild_leader_eligible": PermissionType::GuildLeaderEligible,
+    })
+}
+
+/// Give an adventurer a role in a guild.
+/// Returns whether they didn't already have it.
+///
+/// Becoming a leader starts a leadership term, recording who appointed them, if we know,
+/// and lets them know with a notification.
+/// This doesn't check whether they're allowed to lead guilds; see [`can_lead_guilds`].
+pub(crate) fn assign_guild_role(
+    db: &Transaction,
+    user: UserId,
+    guild: GuildId,
+    role: GuildRole,
+    appointed_by: Option<UserId>,
+) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO AdventurerRole (adventurer_id, guild_id, assigned_role)
+             VALUES (:adventurer_id, :guild_id, :role)
+             ON CONFLICT DO NOTHING;",
+    )?;
+    let n = query.execute(named_params! { ":adventurer_id": user, ":guild_id": guild, ":role": role })?;
+    if n == 1 && role == GuildRole::Leader {
+        let mut query = db.prepare_cached(
+            "INSERT INTO GuildLeadershipTerm (guild_id, adventurer_id, appointed_by, start_date)
+                 VALUES (:guild_id, :adventurer_id, :appointed_by, unixepoch());",
+        )?;
+        query.execute(named_params! { ":guild_id": guild, ":adventurer_id": user, ":appointed_by": appointed_by })?;
+        crate::notification::made_guild_leader(db, user, guild)?;
+    }
+    Ok(n == 1)
+}
+
+/// Take a role in a guild away from an adventurer.
+/// Returns whether they had it.
+///
+/// Taking away the leader role ends their leadership term.
+pub(crate) fn remove_guild_role(db: &Transaction, user: UserId, guild: GuildId, role: GuildRole) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "DELETE FROM AdventurerRole
+             WHERE adventurer_id = :adventurer_id AND guild_id = :guild_id AND assigned_role = :role;",
+    )?;
+    let n = query.execute(named_params! { ":adventurer_id": user, ":guild_id": guild, ":role": role })?;
+    if n == 1 && role == GuildRole::Leader {
+        end_leadership_term(db, user, guild, TermEndReason::Removed)?;
+    }
+    Ok(n == 1)
+}
+
+fn end_leadership_term(db: &Transaction, user: UserId, guild: GuildId, reason: TermEndReason) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "UPDATE GuildLeadershipTerm SET end_date = unixepoch(), end_reason = :reason
+             WHERE guild_id = :guild_id AND adventurer_id = :adventurer_id AND end_date IS NULL;",
+    )?;
+    query.execute(named_params! { ":guild_id": guild, ":adventurer_id": user, ":reason": reason })?;
+    Ok(())
+}
+
+/// Stop an adventurer leading every guild they lead, if they're no longer allowed to lead guilds.
+fn step_down_if_ineligible(db: &Transaction, user: UserId) -> Result<(), rusqlite::Error> {
+    if can_lead_guilds(db, user)? {
+        return Ok(());
+    }
+    let mut query = db.prepare_cached(
+        "DELETE FROM AdventurerRole
+             WHERE adventurer_id = :adventurer_id AND assigned_role = :role
+             RETURNING guild_id;",
+    )?;
+    let guilds = query
+        .query_map(named_params! { ":adventurer_id": user, ":role": GuildRole::Leader }, |row| row.get(0))?
+        .collect::<Result<Vec<GuildId>, _>>()?;
+    for guild in guilds {
+        end_leadership_term(db, user, guild, TermEndReason::Ineligible)?;
+    }
+    Ok(())
+}
+
+/// Check whether any of the roles an adventurer has in a guild give them a capability.
+/// This doesn't take super users into account.
+pub(crate) fn has_guild_capability(
+    db: &Transaction,
+    user: UserId,
+    guild: GuildId,
+    capability: GuildCapability,
+) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT assigned_role FROM AdventurerRole WHERE adventurer_id = :adventurer_id AND guild_id = :guild_id;",
+    )?;
+    for role in query.query_map(named_params! { ":adventurer_id": user, ":guild_id": guild }, |row| {
+        row.get::<_, GuildRole>(0)
+    })? {
+        if role?.capabilities().contains(&capability) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The adventurers who have a role in a guild, in the order they were given it.
+pub(crate) fn guild_role_holders(db: &Transaction, guild: GuildId, role: GuildRole) -> Result<Vec<UserId>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT adventurer_id FROM AdventurerRole
+             WHERE guild_id = :guild_id AND assigned_role = :role
+             ORDER BY id;",
+    )?;
+    let holders = query
+        .query_map(named_params! { ":guild_id": guild, ":role": role }, |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(holders)
+}
+
+/// The leaders of a guild, in the order they became leaders.
+pub(crate) fn guild_leaders(db: &Transaction, guild: GuildId) -> Result<Vec<GuildLeader>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT Adventurer.id, Adventurer.name FROM AdventurerRole
+             INNER JOIN Adventurer ON Adventurer.id = AdventurerRole.adventurer_id
+             WHERE AdventurerRole.guild_id = :guild_id AND AdventurerRole.assigned_role = :role
+             ORDER BY AdventurerRole.id;",
+    )?;
+    let leaders = query
+        .query_map(named_params! { ":guild_id": guild, ":role": GuildRole::Leader }, |row| {
+            Ok(GuildLeader { id: row.get(0)?, name: row.get(1)? })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(leaders)
+}
+
+/// Make an adventurer the only leader of a guild, or leave it without a leader.
+/// Other roles in the guild are left alone.
+pub(crate) fn replace_guild_leaders(
+    db: &Transaction,
+    guild: GuildId,
+    leader: Option<UserId>,
+    appointed_by: Option<UserId>,
+) -> Result<(), rusqlite::Error> {
+    for old_leader in guild_role_holders(db, guild, GuildRole::Leader)? {
+        if Some(old_leader) != leader {
+            remove_guild_role(db, old_leader, guild, GuildRole::Leader)?;
+        }
+    }
+    if let Some(leader) = leader {
+        assign_guild_role(db, leader, guild, GuildRole::Leader, appointed_by)?;
+    }
+    Ok(())
+}
+
+/// If only members of its guild may accept a quest, the guild they must be members of.
+pub(crate) fn members_only_guild(db: &Transaction, quest: QuestId) -> Result<Option<GuildId>, rusqlite::Error> {
+    let mut query =
+        db.prepare_cached("SELECT guild_id FROM Quest WHERE id = :quest_id AND members_only = 1")?;
+    query.query_row(named_params! { ":quest_id": quest }, |row| row.get(0)).optional()
+}
+
+/// Look up which adventurer a login session belongs to.
+// TODO: we are intentionally not checking ttl right now, but
+//  we should in the future, when the client knows how to refresh a session
+pub(crate) fn session_adventurer(db: &Transaction, token: &AuthToken) -> Result<Option<UserId>, rusqlite::Error> {
+    let mut query = db.prepare_cached("SELECT adventurer_id FROM AuthSession WHERE token = :token;")?;
+    query.query_row(named_params! { ":token": token }, |row| row.get(0)).optional()
+}
+
+pub(crate) fn has_permission(db: &Transaction, user: UserId, perm: PermissionType) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT 0 FROM Permission WHERE adventurer_id = :adventurer_id AND permission_type = :permission_type;",
+    )?;
+    query.exists(named_params! { ":adventurer_id": user, ":permission_type": perm })
+}
+
+/// Whether an adventurer may accept a quest right now.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Availability {
+    Available,
+    /// The quest will become available at this Unix timestamp.
+    AvailableAt(i64),
+    /// The quest won't ever be available to this adventurer again.
+    Unavailable,
+}
+impl Availability {
+    fn from_time(available_at: i64, now: i64) -> Self {
+        if available_at > now {
+            Self::AvailableAt(available_at)
+        } else {
+            Self::Available
+        }
+    }
+}
+
+pub(crate) fn quest_repeat_limits(db: &Transaction, quest: QuestId) -> Result<RepeatLimits, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT repeat_cooldown, repeat_limit, repeat_period FROM Quest WHERE id = :quest_id;",
+    )?;
+    query.query_row(named_params! { ":quest_id": quest }, |row| {
+        Ok(RepeatLimits { repeat_cooldown: row.get(0)?, repeat_limit: row.get(1)?, repeat_period: row.get(2)? })
+    })
+}
+
+pub(crate) fn quest_schedule(db: &Transaction, quest: QuestId) -> Result<QuestSchedule, rusqlite::Error> {
+    let mut query = db.prepare_cached("SELECT open_date, close_date FROM Quest WHERE id = :quest_id;")?;
+    query.query_row(named_params! { ":quest_id": quest }, |row| {
+        Ok(QuestSchedule { open_date: row.get(0)?, close_date: row.get(1)? })
+    })
+}
+
+pub(crate) fn quest_completion_days(db: &Transaction, quest: QuestId) -> Result<Option<u32>, rusqlite::Error> {
+    let mut query = db.prepare_cached("SELECT completion_days FROM Quest WHERE id = :quest_id;")?;
+    query.query_row(named_params! { ":quest_id": quest }, |row| row.get(0))
+}
+
+/// Work out whether an adventurer may accept a quest, considering when the quest is scheduled
+/// to be open, and how many times, and how recently, they've accepted it before.
+///
+/// Quests which aren't repeatable may only be accepted once. Repeatable quests
+/// may have a cooldown between acceptances, and a limit on the number of acceptances,
+/// either ever, or within a window of time. Cancelled and abandoned acceptances don't count.
+pub(crate) fn quest_availability(db: &Transaction, user: UserId, quest: QuestId) -> Result<Availability, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT repeatable, repeat_cooldown, repeat_limit, repeat_period, open_date, close_date, unixepoch()
+             FROM Quest WHERE id = :quest_id;",
+    )?;
+    let (repeatable, cooldown, limit, period, open_date, close_date, now) =
+        query.query_row(named_params! { ":quest_id": quest }, |row| {
+            Ok((
+                row.get::<_, bool>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, Option<usize>>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+                row.get::<_, Option<i64>>(5)?,
+                row.get::<_, i64>(6)?,
+            ))
+        })?;
+
+    if close_date.is_some_and(|close_date| close_date <= now) {
+        return Ok(Availability::Unavailable);
+    }
+    let mut available_at = open_date.map_or(now, |open_date| open_date.max(now));
+
+    let accepted = quest_acceptances(db, user, quest)?;
+    let Some(&last) = accepted.last() else {
+        return Ok(Availability::from_time(available_at, now));
+    };
+    if !repeatable {
+        return Ok(Availability::Unavailable);
+    }
+
+    if let Some(cooldown) = cooldown {
+        available_at = available_at.max(last + cooldown);
+    }
+    if let Some(limit) = limit {
+        match period {
+            // Limits of zero are rejected when quests are created or edited,
+            // but there's no room for another acceptance under one either way.
+            _ if limit == 0 => return Ok(Availability::Unavailable),
+            None if accepted.len() >= limit => return Ok(Availability::Unavailable),
+            None => (),
+            Some(period) => {
+                let in_period: Vec<i64> = accepted.into_iter().filter(|&t| t > now - period).collect();
+                if in_period.len() >= limit {
+                    // Enough acceptances have to fall out of the window
+                    // for there to be room for one more.
+                    let oldest_blocking = in_period[in_period.len() - limit];
+                    available_at = available_at.max(oldest_blocking + period);
+                }
+            }
+        }
+    }
+
+    Ok(Availability::from_time(available_at, now))
+}
+
+/// When an adventurer accepted each of their quests for a quest action, oldest first,
+/// leaving out cancelled and abandoned ones.
+fn quest_acceptances(db: &Transaction, user: UserId, quest: QuestId) -> Result<Vec<i64>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT COALESCE(open_date, 0) FROM Quest
+             INNER JOIN PartyMember ON PartyMember.quest_id = Quest.id
+             WHERE parent_quest_id = :quest_id AND adventurer_id = :adventurer_id
+                 AND deleted_date IS NULL AND abandoned_date IS NULL
+             ORDER BY open_date;",
+    )?;
+    let accepted = query
+        .query_map(named_params! { ":quest_id": quest, ":adventurer_id": user }, |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(accepted)
+}
+
+/// Work out whether an adventurer may have one more acceptance of a quest action,
+/// which was accepted at the Unix timestamp `accepted_at`, without going over its repetition limits.
+///
+/// Unlike [`quest_availability`], the quest action's schedule and cooldown aren't considered,
+/// since the acceptance has already happened.
+pub(crate) fn quest_has_room(db: &Transaction, user: UserId, quest: QuestId, accepted_at: i64) -> Result<bool, rusqlite::Error> {
+    let mut query =
+        db.prepare_cached("SELECT repeatable, repeat_limit, repeat_period, unixepoch() FROM Quest WHERE id = :quest_id;")?;
+    let (repeatable, limit, period, now) = query.query_row(named_params! { ":quest_id": quest }, |row| {
+        Ok((
+            row.get::<_, bool>(0)?,
+            row.get::<_, Option<usize>>(1)?,
+            row.get::<_, Option<i64>>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+
+    let accepted = quest_acceptances(db, user, quest)?;
+    if accepted.is_empty() {
+        return Ok(true);
+    }
+    if !repeatable {
+        return Ok(false);
+    }
+    let Some(limit) = limit else {
+        return Ok(true);
+    };
+    Ok(match period {
+        None => accepted.len() < limit,
+        // An acceptance from before the window doesn't count towards it.
+        Some(period) if accepted_at <= now - period => true,
+        Some(period) => accepted.into_iter().filter(|&t| t > now - period).count() < limit,
+    })
+}
+
+/// Get the prerequisites of a guild quest, leaving out any which have been retired.
+pub(crate) fn quest_prerequisites(db: &Transaction, quest: QuestId) -> Result<Vec<QuestId>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT prerequisite_id FROM QuestPrerequisite
+             INNER JOIN Quest ON Quest.id = QuestPrerequisite.prerequisite_id
+             WHERE quest_id = :quest_id AND Quest.deleted_date IS NULL
+             ORDER BY prerequisite_id;",
+    )?;
+    let prerequisites = query
+        .query_map(named_params! { ":quest_id": quest }, |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(prerequisites)
+}
+
+/// Get the prerequisites of a guild quest which an adventurer hasn't completed yet.
+pub(crate) fn unmet_prerequisites(db: &Transaction, user: UserId, quest: QuestId) -> Result<Vec<QuestId>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT prerequisite_id FROM QuestPrerequisite
+             INNER JOIN Quest ON Quest.id = QuestPrerequisite.prerequisite_id
+             WHERE quest_id = :quest_id AND Quest.deleted_date IS NULL
+                 AND NOT EXISTS (SELECT 0 FROM Quest AS Copy
+                                     INNER JOIN PartyMember ON PartyMember.quest_id = Copy.id
+                                     WHERE Copy.parent_quest_id = QuestPrerequisite.prerequisite_id
+                                         AND PartyMember.adventurer_id = :adventurer_id
+                                         AND Copy.close_date IS NOT NULL AND Copy.deleted_date IS NULL)
+             ORDER BY prerequisite_id;",
+    )?;
+    let unmet = query
+        .query_map(named_params! { ":quest_id": quest, ":adventurer_id": user }, |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(unmet)
+}
+
+/// Replace the prerequisites of a guild quest.
+///
+/// This doesn't check whether the new prerequisites would form a cycle,
+/// so use [`prerequisites_form_cycle`] to check before committing.
+pub(crate) fn set_quest_prerequisites(db: &Transaction, quest: QuestId, prerequisites: &[QuestId]) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached("DELETE FROM QuestPrerequisite WHERE quest_id = :quest_id;")?;
+    query.execute(named_params! { ":quest_id": quest })?;
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestPrerequisite (quest_id, prerequisite_id) VALUES (:quest_id, :prerequisite_id)
+             ON CONFLICT DO NOTHING;",
+    )?;
+    for &prerequisite in prerequisites {
+        query.execute(named_params! { ":quest_id": quest, ":prerequisite_id": prerequisite })?;
+    }
+    Ok(())
+}
+
+/// Check whether a guild quest is, directly or indirectly, a prerequisite of itself.
+pub(crate) fn prerequisites_form_cycle(db: &Transaction, quest: QuestId) -> Result<bool, rusqlite::Error> {
+    // Using UNION rather than UNION ALL means each quest is only visited once,
+    // so this terminates even if there's a cycle somewhere else.
+    let mut query = db.prepare_cached(
+        "WITH RECURSIVE
+                 reachable(id) AS (SELECT prerequisite_id FROM QuestPrerequisite WHERE quest_id = :quest_id
+                                   UNION
+                                   SELECT QuestPrerequisite.prerequisite_id FROM QuestPrerequisite
+                                       INNER JOIN reachable ON QuestPrerequisite.quest_id = reachable.id)
+             SELECT 0 FROM reachable WHERE id = :quest_id;",
+    )?;
+    query.exists(named_params! { ":quest_id": quest })
+}
+
+/// Format a Unix timestamp as a date and time for humans to read, in UTC.
+pub(crate) fn format_timestamp(db: &Transaction, timestamp: i64) -> Result<String, rusqlite::Error> {
+    db.query_row("SELECT datetime(:timestamp, 'unixepoch') || ' UTC';", named_params! { ":timestamp": timestamp }, |row| row.get(0))
+}
+
 pub(crate) fn accept_quest(
     db: &Transaction,
     user: UserId,
@@ -62,8 +603,9 @@ pub(crate) fn accept_quest(
 
     // Step 1
     let mut query = db.prepare_cached(
-        "INSERT INTO Quest (guild_id, parent_quest_id, name, quest_type, open_date)
-             SELECT guild_id, :quest_id, name, 1, unixepoch() FROM Quest WHERE id = :quest_id;",
+        "INSERT INTO Quest (guild_id, parent_quest_id, name, quest_type, open_date, due_date)
+             SELECT guild_id, :quest_id, name, 1, unixepoch(), unixepoch() + completion_days * 86400
+             FROM Quest WHERE id = :quest_id;",
     )?;
     query.execute(named_params! { ":quest_id": quest })?;
     let new_id = db.last_insert_rowid();
@@ -95,6 +637,509 @@ pub(crate) fn accept_quest(
     )))
 }
 
+/// Mark every accepted quest which is past its due date, and still hasn't been completed, as abandoned.
+///
+/// Returns the number of quests which were abandoned.
+pub(crate) fn abandon_overdue_quests(db: &Transaction) -> Result<usize, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "UPDATE Quest SET abandoned_date = unixepoch()
+             WHERE due_date <= unixepoch() AND quest_type = 1
+                 AND close_date IS NULL AND deleted_date IS NULL AND abandoned_date IS NULL;",
+    )?;
+    query.execute([])
+}
+
+/// Record the XP earned by every party member of a quest which has just been completed.
+/// Doing this more than once for the same quest has no further effect.
+///
+/// If the quest was completed as part of a campaign, its multiplier and bonus are applied.
+/// When several campaigns apply, the one which awards the most XP wins.
+pub(crate) fn award_quest_xp(db: &Transaction, quest: QuestId) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "WITH
+             completed AS (SELECT id, guild_id, parent_quest_id, close_date,
+                                  (SELECT COALESCE(SUM(xp), 0) FROM QuestTask WHERE QuestTask.quest_id = Quest.id) AS base_xp
+                           FROM Quest
+                           WHERE id = :quest_id AND close_date IS NOT NULL),
+             boost AS (SELECT Campaign.id,
+                              CAST(ROUND(completed.base_xp * Campaign.xp_multiplier) AS INTEGER) + Campaign.xp_bonus AS xp
+                       FROM Campaign, completed
+                       WHERE completed.close_date >= Campaign.start_date AND completed.close_date < Campaign.end_date
+                           AND (EXISTS (SELECT 0 FROM CampaignQuest
+                                            WHERE campaign_id = Campaign.id AND quest_id = completed.parent_quest_id)
+                                OR EXISTS (SELECT 0 FROM CampaignGuild
+                                               WHERE campaign_id = Campaign.id AND guild_id = completed.guild_id))
+                       ORDER BY xp DESC, Campaign.id
+                       LIMIT 1)
+         INSERT INTO XpAward (adventurer_id, guild_id, quest_id, xp, award_date, base_xp, campaign_id)
+             SELECT PartyMember.adventurer_id, completed.guild_id, completed.id,
+                    COALESCE(boost.xp, completed.base_xp), completed.close_date, completed.base_xp, boost.id
+             FROM completed
+                 INNER JOIN PartyMember ON PartyMember.quest_id = completed.id
+                 LEFT OUTER JOIN boost ON true
+             -- This WHERE resolves a parsing ambiguity between the ON of a join and the ON CONFLICT.
+             WHERE true
+             ON CONFLICT DO NOTHING;",
+    )?;
+    query.execute(named_params! { ":quest_id": quest })?;
+    Ok(())
+}
+
+/// Take back any XP awarded for a quest, such as when it gets cancelled,
+/// including XP awarded along with kudos for it.
+pub(crate) fn revoke_quest_xp(db: &Transaction, quest: QuestId) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "DELETE FROM XpAward
+             WHERE quest_id = :quest_id OR kudos_id IN (SELECT id FROM Kudos WHERE quest_id = :quest_id);",
+    )?;
+    query.execute(named_params! { ":quest_id": quest })?;
+    Ok(())
+}
+
+/// Whether a quest has been completed, and an adventurer was one of its party members.
+pub(crate) fn completed_quest_member(db: &Transaction, user: UserId, quest: QuestId) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT 0 FROM Quest
+             INNER JOIN PartyMember ON PartyMember.quest_id = Quest.id
+             WHERE Quest.id = :quest_id AND Quest.quest_type = 1 AND Quest.close_date IS NOT NULL
+                 AND Quest.deleted_date IS NULL AND PartyMember.adventurer_id = :adventurer_id;",
+    )?;
+    query.exists(named_params! { ":quest_id": quest, ":adventurer_id": user })
+}
+
+/// Check whether an adventurer has already sent another kudos for a quest.
+pub(crate) fn kudos_sent_for_quest(db: &Transaction, sender: UserId, recipient: UserId, quest: QuestId) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT 0 FROM Kudos WHERE sender_id = :sender_id AND recipient_id = :recipient_id AND quest_id = :quest_id;",
+    )?;
+    query.exists(named_params! { ":sender_id": sender, ":recipient_id": recipient, ":quest_id": quest })
+}
+
+/// How many kudos an adventurer has sent in the last `seconds` seconds.
+pub(crate) fn recent_kudos_sent(db: &Transaction, sender: UserId, seconds: i64) -> Result<u32, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT COUNT(*) FROM Kudos WHERE sender_id = :sender_id AND sent_date > unixepoch() - :seconds;",
+    )?;
+    query.query_row(named_params! { ":sender_id": sender, ":seconds": seconds }, |row| row.get(0))
+}
+
+/// How many kudos an adventurer has received.
+pub(crate) fn kudos_received_count(db: &Transaction, user: UserId) -> Result<u32, rusqlite::Error> {
+    let mut query = db.prepare_cached("SELECT COUNT(*) FROM Kudos WHERE recipient_id = :recipient_id;")?;
+    query.query_row(named_params! { ":recipient_id": user }, |row| row.get(0))
+}
+
+/// Look up kudos, most recent first, optionally only those sent to one adventurer,
+/// and only those sent before some other kudos, for paging through them.
+pub(crate) fn lookup_kudos(
+    db: &Transaction,
+    recipient: Option<UserId>,
+    before: Option<KudosId>,
+    limit: u32,
+) -> Result<Vec<Kudos>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT Kudos.id, Sender.id, Sender.name, Recipient.id, Recipient.name, Kudos.message,
+                Quest.guild_id, Kudos.quest_id, COALESCE(XpAward.xp, 0), Kudos.sent_date
+             FROM Kudos
+                 INNER JOIN Adventurer AS Sender ON Sender.id = Kudos.sender_id
+                 INNER JOIN Adventurer AS Recipient ON Recipient.id = Kudos.recipient_id
+                 LEFT JOIN Quest ON Quest.id = Kudos.quest_id
+                 LEFT JOIN XpAward ON XpAward.kudos_id = Kudos.id
+             WHERE (:recipient_id IS NULL OR Kudos.recipient_id = :recipient_id)
+                 AND (:before IS NULL OR Kudos.id < :before)
+             ORDER BY Kudos.id DESC
+             LIMIT :limit;",
+    )?;
+    let kudos = query
+        .query_map(named_params! { ":recipient_id": recipient, ":before": before, ":limit": limit }, |row| {
+            Ok(Kudos {
+                id: row.get(0)?,
+                sender_id: row.get(1)?,
+                sender_name: row.get(2)?,
+                recipient_id: row.get(3)?,
+                recipient_name: row.get(4)?,
+                message: row.get(5)?,
+                guild_id: row.get(6)?,
+                quest_id: row.get(7)?,
+                xp: row.get(8)?,
+                sent_date: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(kudos)
+}
+
+/// Record kudos from one adventurer to another.
+/// If the kudos are for a quest, the recipient is awarded `xp` XP in the quest's guild.
+pub(crate) fn send_kudos(
+    db: &Transaction,
+    sender: UserId,
+    recipient: UserId,
+    message: &str,
+    quest: Option<QuestId>,
+    xp: u32,
+) -> Result<KudosId, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO Kudos (sender_id, recipient_id, message, quest_id, sent_date)
+             VALUES (:sender_id, :recipient_id, :message, :quest_id, unixepoch());",
+    )?;
+    query.execute(named_params! {
+        ":sender_id": sender,
+        ":recipient_id": recipient,
+        ":message": message,
+        ":quest_id": quest,
+    })?;
+    let kudos = KudosId(db.last_insert_rowid().try_into().unwrap());
+    if quest.is_some() && xp > 0 {
+        let mut query = db.prepare_cached(
+            "INSERT INTO XpAward (adventurer_id, guild_id, xp, award_date, base_xp, kudos_id)
+                 SELECT :recipient_id, guild_id, :xp, unixepoch(), :xp, :kudos_id FROM Quest WHERE id = :quest_id;",
+        )?;
+        query.execute(named_params! { ":recipient_id": recipient, ":xp": xp, ":kudos_id": kudos, ":quest_id": quest })?;
+    }
+    Ok(kudos)
+}
+
+pub(crate) fn adventurer_total_xp(db: &Transaction, user: UserId) -> Result<u32, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT COALESCE(SUM(xp), 0) FROM XpAward WHERE adventurer_id = :adventurer_id;",
+    )?;
+    query.query_row(named_params! { ":adventurer_id": user }, |row| row.get(0))
+}
+
+/// How much XP an adventurer has left to spend on rewards:
+/// everything they've been awarded, less the cost of every redemption which wasn't cancelled.
+pub(crate) fn points_balance(db: &Transaction, user: UserId) -> Result<i64, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT (SELECT COALESCE(SUM(xp), 0) FROM XpAward WHERE adventurer_id = :adventurer_id)
+              - (SELECT COALESCE(SUM(cost), 0) FROM RewardRedemption
+                     WHERE adventurer_id = :adventurer_id AND status != :cancelled);",
+    )?;
+    query.query_row(
+        named_params! { ":adventurer_id": user, ":cancelled": RedemptionStatus::Cancelled },
+        |row| row.get(0),
+    )
+}
+
+pub(crate) fn lookup_reward(db: &Transaction, reward: RewardId) -> Result<Option<Reward>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT id, name, description, cost, stock, available FROM Reward WHERE id = :reward_id;",
+    )?;
+    query
+        .query_row(named_params! { ":reward_id": reward }, |row| {
+            Ok(Reward {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                cost: row.get(3)?,
+                stock: row.get(4)?,
+                available: row.get(5)?,
+            })
+        })
+        .optional()
+}
+
+/// Record that an adventurer has redeemed a reward, as long as they can afford it.
+/// Returns `None`, and records nothing, if it would leave them with a negative balance.
+///
+/// The balance is checked in the same statement which records the redemption,
+/// so no two redemptions can both spend the same XP.
+pub(crate) fn redeem_reward(db: &Transaction, user: UserId, reward: RewardId, cost: u32) -> Result<Option<RedemptionId>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO RewardRedemption (reward_id, adventurer_id, cost, redeemed_date, status)
+             SELECT :reward_id, :adventurer_id, :cost, unixepoch(), :pending
+             WHERE (SELECT COALESCE(SUM(xp), 0) FROM XpAward WHERE adventurer_id = :adventurer_id)
+                 - (SELECT COALESCE(SUM(cost), 0) FROM RewardRedemption
+                        WHERE adventurer_id = :adventurer_id AND status != :cancelled)
+                 >= :cost;",
+    )?;
+    let n = query.execute(named_params! {
+        ":reward_id": reward,
+        ":adventurer_id": user,
+        ":cost": cost,
+        ":pending": RedemptionStatus::Pending,
+        ":cancelled": RedemptionStatus::Cancelled,
+    })?;
+    if n == 0 {
+        return Ok(None);
+    }
+    Ok(Some(RedemptionId(db.last_insert_rowid().try_into().unwrap())))
+}
+
+/// Take one of a reward out of stock, if it has limited stock.
+/// Returns `false` if it's run out.
+pub(crate) fn take_reward_stock(db: &Transaction, reward: RewardId) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "UPDATE Reward SET stock = stock - 1 WHERE id = :reward_id AND stock > 0;",
+    )?;
+    let n = query.execute(named_params! { ":reward_id": reward })?;
+    if n == 1 {
+        return Ok(true);
+    }
+    let mut query = db.prepare_cached("SELECT 0 FROM Reward WHERE id = :reward_id AND stock IS NULL;")?;
+    query.exists(named_params! { ":reward_id": reward })
+}
+
+/// Mark a pending redemption as fulfilled or cancelled, returning whether it was pending.
+/// Cancelling a redemption refunds its cost, and puts the reward back in stock.
+pub(crate) fn resolve_redemption(
+    db: &Transaction,
+    redemption: RedemptionId,
+    status: RedemptionStatus,
+    resolved_by: UserId,
+) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "UPDATE RewardRedemption SET status = :status, resolved_date = unixepoch(), resolved_by = :resolved_by
+             WHERE id = :redemption_id AND status = :pending
+             RETURNING reward_id;",
+    )?;
+    let reward: Option<RewardId> = query
+        .query_row(
+            named_params! {
+                ":redemption_id": redemption,
+                ":status": status,
+                ":resolved_by": resolved_by,
+                ":pending": RedemptionStatus::Pending,
+            },
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(reward) = reward else {
+        return Ok(false);
+    };
+    if status == RedemptionStatus::Cancelled {
+        let mut query = db.prepare_cached(
+            "UPDATE Reward SET stock = stock + 1 WHERE id = :reward_id AND stock IS NOT NULL;",
+        )?;
+        query.execute(named_params! { ":reward_id": reward })?;
+    }
+    Ok(true)
+}
+
+/// Get the level thresholds configured for a guild, in increasing order.
+///
+/// Guilds which haven't configured any thresholds use the defaults,
+/// which are what you get when passing `None` for the guild.
+pub(crate) fn level_thresholds(db: &Transaction, guild: Option<GuildId>) -> Result<Vec<LevelThreshold>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT level, xp_required FROM LevelThreshold
+             WHERE guild_id IS :guild_id
+             ORDER BY level;",
+    )?;
+    let thresholds = query
+        .query_map(named_params! { ":guild_id": guild }, |row| {
+            Ok(LevelThreshold { level: row.get(0)?, xp_required: row.get(1)? })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    if thresholds.is_empty() && guild.is_some() {
+        level_thresholds(db, None)
+    } else {
+        Ok(thresholds)
+    }
+}
+
+/// Replace the level thresholds for a guild, or the defaults if `guild` is `None`.
+pub(crate) fn set_level_thresholds(db: &Transaction, guild: Option<GuildId>, thresholds: &[LevelThreshold]) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached("DELETE FROM LevelThreshold WHERE guild_id IS :guild_id;")?;
+    query.execute(named_params! { ":guild_id": guild })?;
+    let mut query = db.prepare_cached(
+        "INSERT INTO LevelThreshold (guild_id, level, xp_required)
+             VALUES (:guild_id, :level, :xp_required);",
+    )?;
+    for threshold in thresholds {
+        query.execute(named_params! {
+            ":guild_id": guild,
+            ":level": threshold.level,
+            ":xp_required": threshold.xp_required,
+        })?;
+    }
+    Ok(())
+}
+
+/// Rank adventurers by the XP they've been awarded since a given time,
+/// optionally only counting XP awarded in a single guild.
+///
+/// Rejected adventurers, and those who've opted out, are left off.
+/// Adventurers with the same XP and number of completed actions share a rank,
+/// and are listed in order of their ID.
+pub(crate) fn leaderboard(
+    db: &Transaction,
+    guild: Option<GuildId>,
+    since: i64,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<LeaderboardEntry>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "WITH
+                -- XP and completed quest action count of every adventurer,
+                -- considering only the requested guild and time window.
+                -- XP comes from the ledger, so campaign boosts and kudos count just like they do for levels,
+                -- and kudos awards, which have no quest, aren't counted as completions.
+                Score AS (SELECT adventurer_id,
+                                 SUM(xp) AS xp,
+                                 COUNT(DISTINCT quest_id) AS completed
+                          FROM XpAward
+                          WHERE award_date >= :since
+                              AND (:guild_id IS NULL OR guild_id = :guild_id)
+                          GROUP BY adventurer_id),
+                Ranked AS (SELECT Adventurer.id AS id, Adventurer.name AS name, Score.xp AS xp, Score.completed AS completed,
+                                  RANK() OVER (ORDER BY Score.xp DESC, Score.completed DESC) AS rank
+                           FROM Score
+                               INNER JOIN Adventurer ON Adventurer.id = Score.adventurer_id
+                           WHERE Adventurer.leaderboard_opt_out = 0
+                               AND NOT EXISTS (SELECT 0 FROM Permission
+                                               WHERE Permission.adventurer_id = Adventurer.id AND permission_type = 3))
+             SELECT rank, id, name, xp, completed FROM Ranked
+             ORDER BY rank, id
+             LIMIT :limit OFFSET :offset;",
+    )?;
+    let entries = query
+        .query_map(named_params! {
+            ":guild_id": guild,
+            ":since": since,
+            ":limit": limit,
+            ":offset": offset,
+        }, |row| {
+            Ok(LeaderboardEntry {
+                rank: row.get(0)?,
+                adventurer_id: row.get(1)?,
+                name: row.get(2)?,
+                xp: row.get(3)?,
+                completed_quest_actions: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+pub(crate) fn earned_achievements(db: &Transaction, user: UserId) -> Result<Vec<EarnedAchievement>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT Achievement.id, Achievement.name, Achievement.description, AdventurerAchievement.award_date
+             FROM AdventurerAchievement
+                 INNER JOIN Achievement ON Achievement.id = AdventurerAchievement.achievement_id
+             WHERE AdventurerAchievement.adventurer_id = :adventurer_id
+             ORDER BY AdventurerAchievement.award_date, Achievement.id;",
+    )?;
+    let achievements = query
+        .query_map(named_params! { ":adventurer_id": user }, |row| {
+            Ok(EarnedAchievement {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                award_date: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(achievements)
+}
+
+pub(crate) fn lookup_campaign(db: &Transaction, campaign: CampaignId) -> Result<Option<Campaign>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT name, description, start_date, end_date, xp_multiplier, xp_bonus FROM Campaign WHERE id = :campaign_id;",
+    )?;
+    let Some(mut campaign) = query
+        .query_row(named_params! { ":campaign_id": campaign }, |row| {
+            Ok(Campaign {
+                id: campaign,
+                name: row.get(0)?,
+                description: row.get(1)?,
+                start_date: row.get(2)?,
+                end_date: row.get(3)?,
+                xp_multiplier: row.get(4)?,
+                xp_bonus: row.get(5)?,
+                quest_action_ids: Vec::new(),
+                guild_ids: Vec::new(),
+            })
+        })
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let mut query = db.prepare_cached(
+        "SELECT quest_id FROM CampaignQuest WHERE campaign_id = :campaign_id ORDER BY quest_id;",
+    )?;
+    campaign.quest_action_ids = query
+        .query_map(named_params! { ":campaign_id": campaign.id }, |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut query = db.prepare_cached(
+        "SELECT guild_id FROM CampaignGuild WHERE campaign_id = :campaign_id ORDER BY guild_id;",
+    )?;
+    campaign.guild_ids = query
+        .query_map(named_params! { ":campaign_id": campaign.id }, |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(campaign))
+}
+
+/// Replace the quest actions and guilds which participate in a campaign.
+pub(crate) fn set_campaign_participants(
+    db: &Transaction,
+    campaign: CampaignId,
+    quest_actions: &[QuestId],
+    guilds: &[GuildId],
+) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached("DELETE FROM CampaignQuest WHERE campaign_id = :campaign_id;")?;
+    query.execute(named_params! { ":campaign_id": campaign })?;
+    let mut query = db.prepare_cached("DELETE FROM CampaignGuild WHERE campaign_id = :campaign_id;")?;
+    query.execute(named_params! { ":campaign_id": campaign })?;
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO CampaignQuest (campaign_id, quest_id) VALUES (:campaign_id, :quest_id)
+             ON CONFLICT DO NOTHING;",
+    )?;
+    for &quest in quest_actions {
+        query.execute(named_params! { ":campaign_id": campaign, ":quest_id": quest })?;
+    }
+    let mut query = db.prepare_cached(
+        "INSERT INTO CampaignGuild (campaign_id, guild_id) VALUES (:campaign_id, :guild_id)
+             ON CONFLICT DO NOTHING;",
+    )?;
+    for &guild in guilds {
+        query.execute(named_params! { ":campaign_id": campaign, ":guild_id": guild })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn create_guild_quest_action(
+    db: &Transaction,
+    guild: GuildId,
+    action: &CreateGuildQuestAction,
+) -> Result<QuestId, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO Quest (guild_id, quest_type, repeatable, repeat_cooldown, repeat_limit, repeat_period,
+                            open_date, close_date, completion_days, members_only)
+             VALUES (:guild_id, 0, :repeatable, :repeat_cooldown, :repeat_limit, :repeat_period,
+                     :open_date, :close_date, :completion_days, :members_only);",
+    )?;
+    let n = query.execute(named_params! {
+        ":guild_id": guild,
+        ":repeatable": action.repeatable,
+        ":repeat_cooldown": action.repeat_limits.repeat_cooldown,
+        ":repeat_limit": action.repeat_limits.repeat_limit,
+        ":repeat_period": action.repeat_limits.repeat_period,
+        ":open_date": action.schedule.open_date,
+        ":close_date": action.schedule.close_date,
+        ":completion_days": action.completion_days,
+        ":members_only": action.members_only,
+    })?;
+    assert_eq!(n, 1);
+    let quest_id = db.last_insert_rowid();
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTask (quest_id, order_index, name, description, adventurer_note, xp)
+             VALUES (:quest_id, 0, :name, :description, :adventurer_note, :xp);",
+    )?;
+    let n = query.execute(named_params! {
+        ":quest_id": quest_id,
+        ":name": action.name,
+        ":description": action.description,
+        ":adventurer_note": action.adventurer_note,
+        ":xp": action.xp,
+    })?;
+    assert_eq!(n, 1);
+    Ok(QuestId(quest_id.try_into().unwrap()))
+}
+
 pub(crate) fn lookup_guild_quest_actions(
     db: &Transaction,
     guild: GuildId,
@@ -104,21 +1149,439 @@ pub(crate) fn lookup_guild_quest_actions(
     }
 
     let mut query = db.prepare_cached(
-        "SELECT id, repeatable FROM Quest WHERE guild_id = :guild_id AND deleted_date IS NULL AND quest_type = 0;",
+        "SELECT id FROM Quest WHERE guild_id = :guild_id AND deleted_date IS NULL AND quest_type = 0;",
     )?;
-    let quests = query
-        .query_map(named_params! { ":guild_id": guild }, |row| {
-            let mut query =
-                db.prepare_cached("SELECT name, description, adventurer_note, xp FROM QuestTask WHERE quest_id = :quest_id;")?;
-            let id = row.get(0)?;
-            let repeatable = row.get(1)?;
-            let (name, description, adventurer_note, xp) = query.query_row(named_params! { ":quest_id": id }, |row| {
-                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-            })?;
-            Ok(GuildQuestAction { id, name, description, adventurer_note, xp, repeatable })
+    let ids = query
+        .query_map(named_params! { ":guild_id": guild }, |row| row.get(0))?
+        .collect::<Result<Vec<QuestId>, _>>()?;
+    let mut quests = Vec::with_capacity(ids.len());
+    for id in ids {
+        quests.push(lookup_guild_quest_action(db, id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?);
+    }
+    Ok(Some(quests))
+}
+
+/// Look up a guild quest action which hasn't been retired.
+pub(crate) fn lookup_guild_quest_action(db: &Transaction, quest: QuestId) -> Result<Option<GuildQuestAction>, rusqlite::Error> {
+    lookup_guild_quest_action_where(db, quest, false)
+}
+
+/// Look up a guild quest action, whether or not it has been retired.
+pub(crate) fn lookup_guild_quest_action_including_retired(
+    db: &Transaction,
+    quest: QuestId,
+) -> Result<Option<GuildQuestAction>, rusqlite::Error> {
+    lookup_guild_quest_action_where(db, quest, true)
+}
+
+fn lookup_guild_quest_action_where(
+    db: &Transaction,
+    quest: QuestId,
+    include_retired: bool,
+) -> Result<Option<GuildQuestAction>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT repeatable, repeat_cooldown, repeat_limit, repeat_period, open_date, close_date, completion_days,
+                 (open_date IS NULL OR open_date <= unixepoch()) AND (close_date IS NULL OR close_date > unixepoch()),
+                 members_only
+             FROM Quest
+             WHERE id = :quest_id AND quest_type = 0 AND (:include_retired OR deleted_date IS NULL);",
+    )?;
+    let row = query
+        .query_row(named_params! { ":quest_id": quest, ":include_retired": include_retired }, |row| {
+            let repeat_limits = RepeatLimits {
+                repeat_cooldown: row.get(1)?,
+                repeat_limit: row.get(2)?,
+                repeat_period: row.get(3)?,
+            };
+            let schedule = QuestSchedule { open_date: row.get(4)?, close_date: row.get(5)? };
+            Ok((row.get(0)?, repeat_limits, schedule, row.get(6)?, row.get(7)?, row.get(8)?))
+        })
+        .optional()?;
+    let Some((repeatable, repeat_limits, schedule, completion_days, is_open, members_only)) = row else {
+        return Ok(None);
+    };
+
+    let mut query =
+        db.prepare_cached("SELECT name, description, adventurer_note, xp FROM QuestTask WHERE quest_id = :quest_id;")?;
+    let (name, description, adventurer_note, xp) = query.query_row(named_params! { ":quest_id": quest }, |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+    })?;
+    Ok(Some(GuildQuestAction {
+        id: quest,
+        name,
+        description,
+        adventurer_note,
+        xp,
+        repeatable,
+        repeat_limits,
+        schedule,
+        completion_days,
+        members_only,
+        prerequisites: quest_prerequisites(db, quest)?,
+        tags: quest_tags(db, quest)?,
+        comment_count: quest_comment_count(db, quest)?,
+        is_open,
+    }))
+}
+
+pub(crate) fn quest_tags(db: &Transaction, quest: QuestId) -> Result<Vec<String>, rusqlite::Error> {
+    let mut query = db.prepare_cached("SELECT tag FROM QuestTag WHERE quest_id = :quest_id ORDER BY tag;")?;
+    let tags = query
+        .query_map(named_params! { ":quest_id": quest }, |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tags)
+}
+
+/// Replace the tags of a guild quest.
+pub(crate) fn set_quest_tags(db: &Transaction, quest: QuestId, tags: &[String]) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached("DELETE FROM QuestTag WHERE quest_id = :quest_id;")?;
+    query.execute(named_params! { ":quest_id": quest })?;
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTag (quest_id, tag) VALUES (:quest_id, :tag)
+             ON CONFLICT DO NOTHING;",
+    )?;
+    for tag in tags {
+        query.execute(named_params! { ":quest_id": quest, ":tag": tag })?;
+    }
+    Ok(())
+}
+
+/// How many comments on a guild quest haven't been deleted.
+pub(crate) fn quest_comment_count(db: &Transaction, quest: QuestId) -> Result<u32, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT COUNT(*) FROM QuestComment WHERE quest_id = :quest_id AND deleted_date IS NULL;",
+    )?;
+    query.query_row(named_params! { ":quest_id": quest }, |row| row.get(0))
+}
+
+/// Get every comment on a guild quest, oldest first, including deleted ones,
+/// so that replies to them still have something to point at.
+pub(crate) fn lookup_quest_comments(db: &Transaction, quest: QuestId) -> Result<Vec<QuestComment>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT QuestComment.id, QuestComment.parent_id, QuestComment.author_id, Adventurer.name,
+                QuestComment.body, QuestComment.created_date, QuestComment.edited_date, QuestComment.deleted_date
+             FROM QuestComment
+                 INNER JOIN Adventurer ON Adventurer.id = QuestComment.author_id
+             WHERE QuestComment.quest_id = :quest_id
+             ORDER BY QuestComment.id;",
+    )?;
+    let comments = query
+        .query_map(named_params! { ":quest_id": quest }, |row| {
+            let deleted_date: Option<i64> = row.get(7)?;
+            let deleted = deleted_date.is_some();
+            Ok(QuestComment {
+                id: row.get(0)?,
+                parent_id: row.get(1)?,
+                author_id: if deleted { None } else { row.get(2)? },
+                author_name: if deleted { None } else { row.get(3)? },
+                body: if deleted { None } else { row.get(4)? },
+                created_date: row.get(5)?,
+                edited_date: row.get(6)?,
+                deleted,
+            })
         })?
         .collect::<Result<Vec<_>, _>>()?;
-    Ok(Some(quests))
+    Ok(comments)
+}
+
+/// Look up which quest and guild a comment which hasn't been deleted belongs to, and who wrote it.
+pub(crate) fn lookup_quest_comment(
+    db: &Transaction,
+    comment: CommentId,
+) -> Result<Option<(QuestId, GuildId, UserId)>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT Quest.id, Quest.guild_id, QuestComment.author_id FROM QuestComment
+             INNER JOIN Quest ON Quest.id = QuestComment.quest_id
+             WHERE QuestComment.id = :comment_id AND QuestComment.deleted_date IS NULL;",
+    )?;
+    query
+        .query_row(named_params! { ":comment_id": comment }, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .optional()
+}
+
+/// Comment on a guild quest, or reply to another comment on it.
+pub(crate) fn add_quest_comment(
+    db: &Transaction,
+    quest: QuestId,
+    author: UserId,
+    parent: Option<CommentId>,
+    body: &str,
+) -> Result<CommentId, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestComment (quest_id, author_id, parent_id, body, created_date)
+             VALUES (:quest_id, :author_id, :parent_id, :body, unixepoch());",
+    )?;
+    query.execute(named_params! {
+        ":quest_id": quest,
+        ":author_id": author,
+        ":parent_id": parent,
+        ":body": body,
+    })?;
+    Ok(CommentId(db.last_insert_rowid().try_into().unwrap()))
+}
+
+pub(crate) fn edit_quest_comment(db: &Transaction, comment: CommentId, body: &str) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "UPDATE QuestComment SET body = :body, edited_date = unixepoch() WHERE id = :comment_id;",
+    )?;
+    query.execute(named_params! { ":comment_id": comment, ":body": body })?;
+    Ok(())
+}
+
+/// Mark a comment as deleted, keeping it around so replies to it still make sense.
+pub(crate) fn delete_quest_comment(db: &Transaction, comment: CommentId, deleted_by: UserId) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "UPDATE QuestComment SET deleted_date = unixepoch(), deleted_by = :deleted_by WHERE id = :comment_id;",
+    )?;
+    query.execute(named_params! { ":comment_id": comment, ":deleted_by": deleted_by })?;
+    Ok(())
+}
+
+/// Copy a guild quest action, along with its tasks, details, and tags, into a guild.
+/// Its prerequisites are only kept when the copy is in the same guild, since they're
+/// quest actions of that guild.
+///
+/// The copy doesn't get a `parent_quest_id`, since that's how we find the copies of a quest action
+/// which adventurers have accepted.
+pub(crate) fn copy_guild_quest_action(db: &Transaction, quest: QuestId, guild: GuildId) -> Result<QuestId, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO Quest (guild_id, name, quest_type, repeatable, repeat_cooldown, repeat_limit, repeat_period,
+                            open_date, close_date, completion_days, members_only)
+             SELECT :guild_id, name, 0, repeatable, repeat_cooldown, repeat_limit, repeat_period,
+                    open_date, close_date, completion_days, members_only
+             FROM Quest WHERE id = :quest_id;",
+    )?;
+    let n = query.execute(named_params! { ":guild_id": guild, ":quest_id": quest })?;
+    assert_eq!(n, 1);
+    let new_id = db.last_insert_rowid();
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTask (quest_id, order_index, name, description, adventurer_note, xp)
+             SELECT :new_id, order_index, name, description, adventurer_note, xp FROM QuestTask
+             WHERE quest_id = :quest_id ORDER BY order_index, id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":quest_id": quest })?;
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestDetail (quest_id, description)
+             SELECT :new_id, description FROM QuestDetail WHERE quest_id = :quest_id ORDER BY id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":quest_id": quest })?;
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTag (quest_id, tag)
+             SELECT :new_id, tag FROM QuestTag WHERE quest_id = :quest_id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":quest_id": quest })?;
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestPrerequisite (quest_id, prerequisite_id)
+             SELECT :new_id, prerequisite_id FROM QuestPrerequisite
+             INNER JOIN Quest ON Quest.id = QuestPrerequisite.quest_id
+             WHERE QuestPrerequisite.quest_id = :quest_id AND Quest.guild_id = :guild_id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":quest_id": quest, ":guild_id": guild })?;
+
+    Ok(QuestId(new_id.try_into().unwrap()))
+}
+
+pub(crate) fn quest_template_exists(db: &Transaction, template: QuestTemplateId) -> Result<bool, rusqlite::Error> {
+    let mut query =
+        db.prepare_cached("SELECT 0 FROM QuestTemplate WHERE id = :id AND deleted_date IS NULL")?;
+    query.exists(named_params! { ":id": template })
+}
+
+/// Look up a quest template which hasn't been deleted.
+///
+/// Like [`GuildQuestAction`], its name and XP are those of its first task.
+pub(crate) fn lookup_quest_template(db: &Transaction, template: QuestTemplateId) -> Result<Option<QuestTemplate>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT QuestTemplateTask.name, QuestTemplateTask.description, QuestTemplateTask.adventurer_note, QuestTemplateTask.xp,
+                repeatable, repeat_cooldown, repeat_limit, repeat_period, completion_days,
+                (SELECT COUNT(*) FROM QuestTemplateTask WHERE template_id = QuestTemplate.id)
+             FROM QuestTemplate
+             INNER JOIN QuestTemplateTask ON QuestTemplateTask.template_id = QuestTemplate.id
+             WHERE QuestTemplate.id = :template_id AND deleted_date IS NULL
+             ORDER BY order_index, QuestTemplateTask.id
+             LIMIT 1;",
+    )?;
+    let template = query
+        .query_row(named_params! { ":template_id": template }, |row| {
+            Ok(QuestTemplate {
+                id: template,
+                name: row.get(0)?,
+                description: row.get(1)?,
+                adventurer_note: row.get(2)?,
+                xp: row.get(3)?,
+                repeatable: row.get(4)?,
+                repeat_limits: RepeatLimits {
+                    repeat_cooldown: row.get(5)?,
+                    repeat_limit: row.get(6)?,
+                    repeat_period: row.get(7)?,
+                },
+                completion_days: row.get(8)?,
+                tasks: row.get(9)?,
+                tags: Vec::new(),
+            })
+        })
+        .optional()?;
+    let Some(mut template) = template else {
+        return Ok(None);
+    };
+
+    let mut query =
+        db.prepare_cached("SELECT tag FROM QuestTemplateTag WHERE template_id = :template_id ORDER BY tag;")?;
+    template.tags = query
+        .query_map(named_params! { ":template_id": template.id }, |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(template))
+}
+
+/// Replace the tags of a quest template.
+pub(crate) fn set_quest_template_tags(db: &Transaction, template: QuestTemplateId, tags: &[String]) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached("DELETE FROM QuestTemplateTag WHERE template_id = :template_id;")?;
+    query.execute(named_params! { ":template_id": template })?;
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTemplateTag (template_id, tag) VALUES (:template_id, :tag)
+             ON CONFLICT DO NOTHING;",
+    )?;
+    for tag in tags {
+        query.execute(named_params! { ":template_id": template, ":tag": tag })?;
+    }
+    Ok(())
+}
+
+/// Make a quest template out of a guild quest action, with the same tasks, details, and tags.
+/// Its schedule and prerequisites are left behind, since they only make sense in its own guild.
+pub(crate) fn create_quest_template_from_quest(db: &Transaction, quest: QuestId) -> Result<QuestTemplateId, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTemplate (name, repeatable, repeat_cooldown, repeat_limit, repeat_period, completion_days, created_date)
+             SELECT name, repeatable, repeat_cooldown, repeat_limit, repeat_period, completion_days, unixepoch()
+             FROM Quest WHERE id = :quest_id;",
+    )?;
+    let n = query.execute(named_params! { ":quest_id": quest })?;
+    assert_eq!(n, 1);
+    let new_id = db.last_insert_rowid();
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTemplateTask (template_id, order_index, name, description, adventurer_note, xp)
+             SELECT :new_id, order_index, name, description, adventurer_note, xp FROM QuestTask
+             WHERE quest_id = :quest_id ORDER BY order_index, id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":quest_id": quest })?;
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTemplateDetail (template_id, description)
+             SELECT :new_id, description FROM QuestDetail WHERE quest_id = :quest_id ORDER BY id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":quest_id": quest })?;
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTemplateTag (template_id, tag)
+             SELECT :new_id, tag FROM QuestTag WHERE quest_id = :quest_id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":quest_id": quest })?;
+
+    Ok(QuestTemplateId(new_id.try_into().unwrap()))
+}
+
+/// Create a new quest action in a guild from a quest template,
+/// with the same tasks, details, and tags.
+pub(crate) fn instantiate_quest_template(db: &Transaction, template: QuestTemplateId, guild: GuildId) -> Result<QuestId, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO Quest (guild_id, name, quest_type, repeatable, repeat_cooldown, repeat_limit, repeat_period, completion_days)
+             SELECT :guild_id, name, 0, repeatable, repeat_cooldown, repeat_limit, repeat_period, completion_days
+             FROM QuestTemplate WHERE id = :template_id;",
+    )?;
+    let n = query.execute(named_params! { ":guild_id": guild, ":template_id": template })?;
+    assert_eq!(n, 1);
+    let new_id = db.last_insert_rowid();
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTask (quest_id, order_index, name, description, adventurer_note, xp)
+             SELECT :new_id, order_index, name, description, adventurer_note, xp FROM QuestTemplateTask
+             WHERE template_id = :template_id ORDER BY order_index, id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":template_id": template })?;
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestDetail (quest_id, description)
+             SELECT :new_id, description FROM QuestTemplateDetail WHERE template_id = :template_id ORDER BY id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":template_id": template })?;
+
+    let mut query = db.prepare_cached(
+        "INSERT INTO QuestTag (quest_id, tag)
+             SELECT :new_id, tag FROM QuestTemplateTag WHERE template_id = :template_id;",
+    )?;
+    query.execute(named_params! { ":new_id": new_id, ":template_id": template })?;
+
+    Ok(QuestId(new_id.try_into().unwrap()))
+}
+
+/// The metadata we keep about a file attached to a quest task.
+/// The contents of the file are kept in the [blob store](crate::blob).
+pub(crate) struct AttachmentRecord {
+    pub(crate) quest_task_id: QuestTaskId,
+    pub(crate) adventurer_id: UserId,
+    pub(crate) blob_key: String,
+    pub(crate) file_name: String,
+    pub(crate) content_type: String,
+}
+
+pub(crate) fn lookup_attachment(db: &Transaction, attachment: AttachmentId) -> Result<Option<AttachmentRecord>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT quest_task_id, adventurer_id, blob_key, file_name, content_type
+             FROM QuestTaskAttachment WHERE id = :attachment_id;",
+    )?;
+    query.query_row(named_params! { ":attachment_id": attachment }, |row| {
+        Ok(AttachmentRecord {
+            quest_task_id: row.get(0)?,
+            adventurer_id: row.get(1)?,
+            blob_key: row.get(2)?,
+            file_name: row.get(3)?,
+            content_type: row.get(4)?,
+        })
+    }).optional()
+}
+
+/// Check whether a user may see the attachments on a quest task.
+/// That's allowed for the task's party members, those who may review quests for the task's guild,
+/// and super users.
+pub(crate) fn can_view_quest_task_attachments(db: &Transaction, viewer: UserId, task: QuestTaskId) -> Result<bool, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "SELECT Quest.guild_id,
+                EXISTS (SELECT 0 FROM PartyMember WHERE PartyMember.quest_id = Quest.id AND adventurer_id = :viewer_id)
+             FROM QuestTask
+                 INNER JOIN Quest ON Quest.id = QuestTask.quest_id
+             WHERE QuestTask.id = :task_id;",
+    )?;
+    let task = query
+        .query_row(named_params! { ":task_id": task, ":viewer_id": viewer }, |row| {
+            Ok((row.get::<_, GuildId>(0)?, row.get::<_, bool>(1)?))
+        })
+        .optional()?;
+    let Some((guild, party_member)) = task else {
+        return Ok(false);
+    };
+    Ok(party_member
+        || has_guild_capability(db, viewer, guild, GuildCapability::ReviewQuests)?
+        || has_permission(db, viewer, PermissionType::SuperUser)?)
+}
+
+/// Delete the records of every file attached to the tasks of a quest,
+/// returning the blob keys of the files, so they can be removed from the blob store
+/// once the transaction has been committed.
+pub(crate) fn remove_quest_attachments(db: &Transaction, quest: QuestId) -> Result<Vec<String>, rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "DELETE FROM QuestTaskAttachment
+             WHERE quest_task_id IN (SELECT id FROM QuestTask WHERE quest_id = :quest_id)
+             RETURNING blob_key;",
+    )?;
+    let keys = query
+        .query_map(named_params! { ":quest_id": quest }, |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(keys)
 }
 
 pub(crate) fn set_user_permission(
@@ -168,6 +1631,9 @@ pub(crate) fn set_user_permission(
         let n =
             query.execute(named_params! { ":adventurer_id": user, ":permission_type": perm })?;
         assert!(n <= 1);
+
+        // losing the permission which let them lead guilds ends their terms as a leader
+        step_down_if_ineligible(db, user)?;
     }
 
     Ok(())
@@ -240,3 +1706,171 @@ str_wrap!(Email);
 #[serde(transparent)]
 pub(crate) struct Name(pub String);
 str_wrap!(Name);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    /// Set up a guild quest with the given repeat settings, and an adventurer to accept it.
+    fn setup(db: &Transaction, repeatable: bool, cooldown: Option<i64>, limit: Option<i64>, period: Option<i64>) -> (UserId, QuestId) {
+        db.execute_batch(
+            "INSERT INTO Guild (id, name) VALUES (1, 'Guild');
+             INSERT INTO Adventurer (id, name, email_address, password_hash, password_salt)
+                 VALUES (1, 'Adventurer', 'adventurer@example.com', '', '');",
+        )
+        .unwrap();
+        db.execute(
+            "INSERT INTO Quest (id, guild_id, quest_type, repeatable, repeat_cooldown, repeat_limit, repeat_period)
+                 VALUES (1, 1, 0, ?1, ?2, ?3, ?4);",
+            (repeatable, cooldown, limit, period),
+        )
+        .unwrap();
+        (UserId(1), QuestId(1))
+    }
+
+    /// Record that the adventurer accepted the quest at a given time.
+    fn accept_at(db: &Transaction, user: UserId, quest: QuestId, time: i64) {
+        db.execute(
+            "INSERT INTO Quest (guild_id, parent_quest_id, quest_type, open_date) SELECT guild_id, id, 1, ?2 FROM Quest WHERE id = ?1;",
+            (quest, time),
+        )
+        .unwrap();
+        db.execute("INSERT INTO PartyMember (adventurer_id, quest_id) VALUES (?1, ?2);", (user, db.last_insert_rowid()))
+            .unwrap();
+    }
+
+    fn now(db: &Transaction) -> i64 {
+        db.query_row("SELECT unixepoch();", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn quests_which_are_not_repeatable_are_accepted_once() {
+        let mut db = open_in_memory();
+        let db = db.transaction().unwrap();
+        let (user, quest) = setup(&db, false, None, None, None);
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::Available);
+        accept_at(&db, user, quest, now(&db) - DAY);
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::Unavailable);
+    }
+
+    #[test]
+    fn cooldown_starts_at_the_last_acceptance() {
+        let mut db = open_in_memory();
+        let db = db.transaction().unwrap();
+        let (user, quest) = setup(&db, true, Some(DAY), None, None);
+        let now = now(&db);
+        accept_at(&db, user, quest, now - 3 * DAY);
+        accept_at(&db, user, quest, now - 100);
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::AvailableAt(now - 100 + DAY));
+    }
+
+    #[test]
+    fn limit_without_a_period_applies_forever() {
+        let mut db = open_in_memory();
+        let db = db.transaction().unwrap();
+        let (user, quest) = setup(&db, true, None, Some(2), None);
+        let now = now(&db);
+        accept_at(&db, user, quest, now - 100 * DAY);
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::Available);
+        accept_at(&db, user, quest, now - 50 * DAY);
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::Unavailable);
+    }
+
+    #[test]
+    fn limit_within_a_period_waits_for_the_oldest_acceptance_to_fall_out() {
+        let mut db = open_in_memory();
+        let db = db.transaction().unwrap();
+        let (user, quest) = setup(&db, true, None, Some(2), Some(7 * DAY));
+        let now = now(&db);
+        // Outside the window, so it doesn't count.
+        accept_at(&db, user, quest, now - 8 * DAY);
+        accept_at(&db, user, quest, now - 5 * DAY);
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::Available);
+        accept_at(&db, user, quest, now - DAY);
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::AvailableAt(now - 5 * DAY + 7 * DAY));
+    }
+
+    #[test]
+    fn zero_limit_is_unavailable_instead_of_panicking() {
+        let mut db = open_in_memory();
+        let db = db.transaction().unwrap();
+        let (user, quest) = setup(&db, true, None, Some(0), Some(DAY));
+        accept_at(&db, user, quest, now(&db) - 10 * DAY);
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::Unavailable);
+    }
+
+    #[test]
+    fn schedule_bounds_availability() {
+        let mut db = open_in_memory();
+        let db = db.transaction().unwrap();
+        let (user, quest) = setup(&db, true, None, None, None);
+        let now = now(&db);
+        db.execute("UPDATE Quest SET open_date = ?1 WHERE id = 1;", [now + DAY]).unwrap();
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::AvailableAt(now + DAY));
+        db.execute("UPDATE Quest SET open_date = NULL, close_date = ?1 WHERE id = 1;", [now - 1]).unwrap();
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::Unavailable);
+    }
+
+    #[test]
+    fn restoring_ignores_schedule_and_cooldown_but_not_limits() {
+        let mut db = open_in_memory();
+        let db = db.transaction().unwrap();
+        let (user, quest) = setup(&db, true, Some(7 * DAY), Some(2), None);
+        let now = now(&db);
+        db.execute("UPDATE Quest SET close_date = ?1 WHERE id = 1;", [now - 1]).unwrap();
+        accept_at(&db, user, quest, now - DAY);
+        assert_eq!(quest_availability(&db, user, quest).unwrap(), Availability::Unavailable);
+        assert!(quest_has_room(&db, user, quest, now - 2 * DAY).unwrap());
+        accept_at(&db, user, quest, now - DAY);
+        assert!(!quest_has_room(&db, user, quest, now - 2 * DAY).unwrap());
+    }
+
+    #[test]
+    fn guild_capabilities_follow_roles() {
+        let mut db = open_in_memory();
+        let db = db.transaction().unwrap();
+        let (user, _) = setup(&db, false, None, None, None);
+        db.execute("INSERT INTO Guild (id, name) VALUES (2, 'Other Guild');", []).unwrap();
+        let (guild, other_guild) = (GuildId(1), GuildId(2));
+        assert!(!has_guild_capability(&db, user, guild, GuildCapability::ReviewQuests).unwrap());
+
+        assign_guild_role(&db, user, guild, GuildRole::Reviewer, None).unwrap();
+        assert!(has_guild_capability(&db, user, guild, GuildCapability::ReviewQuests).unwrap());
+        assert!(!has_guild_capability(&db, user, guild, GuildCapability::ManageQuestActions).unwrap());
+        assert!(!has_guild_capability(&db, user, other_guild, GuildCapability::ReviewQuests).unwrap());
+
+        assign_guild_role(&db, user, guild, GuildRole::Officer, None).unwrap();
+        assert!(has_guild_capability(&db, user, guild, GuildCapability::ManageQuestActions).unwrap());
+        assert!(!has_guild_capability(&db, user, guild, GuildCapability::ManageRoles).unwrap());
+    }
+
+    #[test]
+    fn kudos_for_a_quest_can_only_be_sent_once() {
+        let mut db = open_in_memory();
+        let db = db.transaction().unwrap();
+        let (sender, quest) = setup(&db, false, None, None, None);
+        db.execute(
+            "INSERT INTO Adventurer (id, name, email_address, password_hash, password_salt)
+                 VALUES (2, 'Recipient', 'recipient@example.com', '', '');",
+            [],
+        )
+        .unwrap();
+        let recipient = UserId(2);
+
+        assert!(!kudos_sent_for_quest(&db, sender, recipient, quest).unwrap());
+        send_kudos(&db, sender, recipient, "Thanks!", Some(quest), 5).unwrap();
+        assert!(kudos_sent_for_quest(&db, sender, recipient, quest).unwrap());
+        assert!(!kudos_sent_for_quest(&db, recipient, sender, quest).unwrap());
+        assert!(send_kudos(&db, sender, recipient, "Thanks again!", Some(quest), 5).is_err());
+        assert_eq!(adventurer_total_xp(&db, recipient).unwrap(), 5);
+
+        // Kudos which aren't for a quest don't award XP, so they can be sent as often as the daily limit allows.
+        send_kudos(&db, sender, recipient, "Thanks!", None, 0).unwrap();
+        send_kudos(&db, sender, recipient, "Thanks!", None, 0).unwrap();
+        assert_eq!(recent_kudos_sent(&db, sender, DAY).unwrap(), 3);
+        assert_eq!(recent_kudos_sent(&db, recipient, DAY).unwrap(), 0);
+        assert_eq!(adventurer_total_xp(&db, recipient).unwrap(), 5);
+    }
+}
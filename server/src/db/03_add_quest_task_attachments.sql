@@ -0,0 +1,17 @@
+-- Adds storage for files which adventurers attach to their quest tasks,
+-- as evidence of how they completed them.
+
+CREATE TABLE QuestTaskAttachment (
+    id INTEGER PRIMARY KEY,
+    quest_task_id INTEGER NOT NULL REFERENCES QuestTask (id),
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    -- The file contents are kept in the blob store, under this key.
+    blob_key TEXT NOT NULL UNIQUE,
+    file_name TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    -- size is stored in bytes
+    size INTEGER NOT NULL,
+    upload_date INTEGER NOT NULL
+) STRICT;
+
+PRAGMA user_version = 3;
@@ -0,0 +1,41 @@
+-- Adds a ledger of XP awarded to adventurers, and level thresholds.
+
+CREATE TABLE XpAward (
+    id INTEGER PRIMARY KEY,
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    guild_id INTEGER NOT NULL REFERENCES Guild (id),
+    -- The accepted quest this XP was awarded for completing.
+    quest_id INTEGER REFERENCES Quest (id),
+    xp INTEGER NOT NULL,
+    award_date INTEGER NOT NULL,
+    UNIQUE(adventurer_id, quest_id)
+) STRICT;
+
+CREATE TABLE LevelThreshold (
+    id INTEGER PRIMARY KEY,
+    guild_id INTEGER REFERENCES Guild (id),
+    level INTEGER NOT NULL,
+    xp_required INTEGER NOT NULL
+) STRICT;
+
+-- Award XP for everything which was completed before we had a ledger.
+INSERT INTO XpAward (adventurer_id, guild_id, quest_id, xp, award_date)
+    SELECT PartyMember.adventurer_id, Quest.guild_id, Quest.id,
+           (SELECT COALESCE(SUM(xp), 0) FROM QuestTask WHERE QuestTask.quest_id = Quest.id),
+           Quest.close_date
+    FROM Quest
+        INNER JOIN PartyMember ON PartyMember.quest_id = Quest.id
+    WHERE Quest.quest_type = 1 AND Quest.close_date IS NOT NULL AND Quest.deleted_date IS NULL;
+
+INSERT INTO LevelThreshold (guild_id, level, xp_required)
+VALUES
+(NULL, 1, 0),
+(NULL, 2, 100),
+(NULL, 3, 250),
+(NULL, 4, 500),
+(NULL, 5, 1000),
+(NULL, 6, 2000),
+(NULL, 7, 3500),
+(NULL, 8, 5000);
+
+PRAGMA user_version = 4;
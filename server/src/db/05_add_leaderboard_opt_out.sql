@@ -0,0 +1,10 @@
+-- Adds the ability for adventurers to keep themselves off of leaderboards,
+-- and indexes to keep leaderboard queries quick.
+
+ALTER TABLE Adventurer ADD COLUMN
+leaderboard_opt_out INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX PartyMember_quest_id ON PartyMember (quest_id);
+CREATE INDEX Quest_close_date ON Quest (close_date);
+
+PRAGMA user_version = 5;
@@ -0,0 +1,21 @@
+-- Adds achievements, and a record of which adventurers have earned them.
+
+CREATE TABLE Achievement (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    criteria_type INTEGER NOT NULL,
+    guild_id INTEGER REFERENCES Guild (id),
+    count INTEGER,
+    xp INTEGER
+) STRICT;
+
+CREATE TABLE AdventurerAchievement (
+    id INTEGER PRIMARY KEY,
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    achievement_id INTEGER NOT NULL REFERENCES Achievement (id),
+    award_date INTEGER NOT NULL,
+    UNIQUE(adventurer_id, achievement_id)
+) STRICT;
+
+PRAGMA user_version = 6;
@@ -0,0 +1,10 @@
+-- Adds cooldowns and repetition caps to repeatable quests.
+
+ALTER TABLE Quest ADD COLUMN
+repeat_cooldown INTEGER;
+ALTER TABLE Quest ADD COLUMN
+repeat_limit INTEGER;
+ALTER TABLE Quest ADD COLUMN
+repeat_period INTEGER;
+
+PRAGMA user_version = 7;
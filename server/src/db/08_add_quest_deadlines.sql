@@ -0,0 +1,12 @@
+-- Adds deadlines to accepted quests, and lets them expire.
+
+ALTER TABLE Quest ADD COLUMN
+completion_days INTEGER;
+ALTER TABLE Quest ADD COLUMN
+due_date INTEGER;
+ALTER TABLE Quest ADD COLUMN
+abandoned_date INTEGER;
+
+CREATE INDEX Quest_due_date ON Quest (due_date);
+
+PRAGMA user_version = 8;
@@ -0,0 +1,9 @@
+-- Adds prerequisites to guild quests, which lets them be arranged into chains.
+
+CREATE TABLE QuestPrerequisite (
+    quest_id INTEGER NOT NULL REFERENCES Quest (id),
+    prerequisite_id INTEGER NOT NULL REFERENCES Quest (id),
+    PRIMARY KEY (quest_id, prerequisite_id)
+) STRICT;
+
+PRAGMA user_version = 9;
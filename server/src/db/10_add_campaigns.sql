@@ -0,0 +1,33 @@
+-- Adds campaigns, which boost the XP awarded for some quests for a while.
+
+CREATE TABLE Campaign (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    start_date INTEGER NOT NULL,
+    end_date INTEGER NOT NULL,
+    xp_multiplier REAL NOT NULL DEFAULT 1.0,
+    xp_bonus INTEGER NOT NULL DEFAULT 0
+) STRICT;
+
+CREATE TABLE CampaignQuest (
+    campaign_id INTEGER NOT NULL REFERENCES Campaign (id),
+    quest_id INTEGER NOT NULL REFERENCES Quest (id),
+    PRIMARY KEY (campaign_id, quest_id)
+) STRICT;
+
+CREATE TABLE CampaignGuild (
+    campaign_id INTEGER NOT NULL REFERENCES Campaign (id),
+    guild_id INTEGER NOT NULL REFERENCES Guild (id),
+    PRIMARY KEY (campaign_id, guild_id)
+) STRICT;
+
+ALTER TABLE XpAward ADD COLUMN
+base_xp INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE XpAward ADD COLUMN
+campaign_id INTEGER REFERENCES Campaign (id);
+
+-- Nothing awarded so far was part of a campaign.
+UPDATE XpAward SET base_xp = xp;
+
+PRAGMA user_version = 10;
@@ -0,0 +1,10 @@
+-- Adds tags to guild quests, so they can be grouped into categories and searched.
+
+CREATE TABLE QuestTag (
+    quest_id INTEGER NOT NULL REFERENCES Quest (id),
+    tag TEXT NOT NULL,
+    PRIMARY KEY (quest_id, tag)
+) STRICT;
+CREATE INDEX QuestTag_tag ON QuestTag (tag);
+
+PRAGMA user_version = 11;
@@ -0,0 +1,64 @@
+-- Adds full-text search over quest tasks, quest details, and guilds.
+-- The search tables are kept in sync with the tables they index by triggers.
+
+CREATE VIRTUAL TABLE QuestTaskSearch USING fts5(name, description, adventurer_note);
+
+CREATE TRIGGER QuestTaskSearch_insert AFTER INSERT ON QuestTask BEGIN
+    INSERT INTO QuestTaskSearch (rowid, name, description, adventurer_note)
+        SELECT NEW.id,
+               iif(Quest.quest_type = 0, NEW.name, NULL),
+               iif(Quest.quest_type = 0, NEW.description, NULL),
+               NEW.adventurer_note
+        FROM Quest WHERE Quest.id = NEW.quest_id;
+END;
+CREATE TRIGGER QuestTaskSearch_update AFTER UPDATE OF name, description, adventurer_note ON QuestTask BEGIN
+    DELETE FROM QuestTaskSearch WHERE rowid = OLD.id;
+    INSERT INTO QuestTaskSearch (rowid, name, description, adventurer_note)
+        SELECT NEW.id,
+               iif(Quest.quest_type = 0, NEW.name, NULL),
+               iif(Quest.quest_type = 0, NEW.description, NULL),
+               NEW.adventurer_note
+        FROM Quest WHERE Quest.id = NEW.quest_id;
+END;
+CREATE TRIGGER QuestTaskSearch_delete AFTER DELETE ON QuestTask BEGIN
+    DELETE FROM QuestTaskSearch WHERE rowid = OLD.id;
+END;
+
+CREATE VIRTUAL TABLE QuestDetailSearch USING fts5(description);
+
+CREATE TRIGGER QuestDetailSearch_insert AFTER INSERT ON QuestDetail BEGIN
+    INSERT INTO QuestDetailSearch (rowid, description) VALUES (NEW.id, NEW.description);
+END;
+CREATE TRIGGER QuestDetailSearch_update AFTER UPDATE OF description ON QuestDetail BEGIN
+    UPDATE QuestDetailSearch SET description = NEW.description WHERE rowid = OLD.id;
+END;
+CREATE TRIGGER QuestDetailSearch_delete AFTER DELETE ON QuestDetail BEGIN
+    DELETE FROM QuestDetailSearch WHERE rowid = OLD.id;
+END;
+
+CREATE VIRTUAL TABLE GuildSearch USING fts5(name);
+
+CREATE TRIGGER GuildSearch_insert AFTER INSERT ON Guild BEGIN
+    INSERT INTO GuildSearch (rowid, name) VALUES (NEW.id, NEW.name);
+END;
+CREATE TRIGGER GuildSearch_update AFTER UPDATE OF name ON Guild BEGIN
+    UPDATE GuildSearch SET name = NEW.name WHERE rowid = OLD.id;
+END;
+CREATE TRIGGER GuildSearch_delete AFTER DELETE ON Guild BEGIN
+    DELETE FROM GuildSearch WHERE rowid = OLD.id;
+END;
+
+-- Index everything which existed before we had search.
+INSERT INTO QuestTaskSearch (rowid, name, description, adventurer_note)
+    SELECT QuestTask.id,
+           iif(Quest.quest_type = 0, QuestTask.name, NULL),
+           iif(Quest.quest_type = 0, QuestTask.description, NULL),
+           QuestTask.adventurer_note
+    FROM QuestTask
+        INNER JOIN Quest ON Quest.id = QuestTask.quest_id;
+INSERT INTO QuestDetailSearch (rowid, description)
+    SELECT id, description FROM QuestDetail;
+INSERT INTO GuildSearch (rowid, name)
+    SELECT id, name FROM Guild;
+
+PRAGMA user_version = 12;
@@ -0,0 +1,40 @@
+-- Adds a library of quest templates, which guild leaders can copy into their guilds.
+
+-- Quest templates are quest actions which don't belong to any guild.
+-- Super users curate them, and guild leaders copy them into their guilds as new quest actions.
+-- The columns mean the same as they do on Quest and the tables hanging off of it.
+CREATE TABLE QuestTemplate (
+    id INTEGER PRIMARY KEY,
+    name TEXT,
+    repeatable INTEGER NOT NULL DEFAULT 0,
+    repeat_cooldown INTEGER,
+    repeat_limit INTEGER,
+    repeat_period INTEGER,
+    completion_days INTEGER,
+    created_date INTEGER NOT NULL,
+    deleted_date INTEGER
+) STRICT;
+
+CREATE TABLE QuestTemplateTask (
+    id INTEGER PRIMARY KEY,
+    template_id INTEGER NOT NULL REFERENCES QuestTemplate (id),
+    order_index INTEGER NOT NULL DEFAULT 0,
+    name TEXT NOT NULL,
+    description TEXT,
+    adventurer_note TEXT,
+    xp INTEGER NOT NULL
+) STRICT;
+
+CREATE TABLE QuestTemplateDetail (
+    id INTEGER PRIMARY KEY,
+    template_id INTEGER NOT NULL REFERENCES QuestTemplate (id),
+    description TEXT NOT NULL
+) STRICT;
+
+CREATE TABLE QuestTemplateTag (
+    template_id INTEGER NOT NULL REFERENCES QuestTemplate (id),
+    tag TEXT NOT NULL,
+    PRIMARY KEY (template_id, tag)
+) STRICT;
+
+PRAGMA user_version = 13;
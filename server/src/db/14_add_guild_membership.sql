@@ -0,0 +1,14 @@
+-- Adds guild membership, which is separate from leadership,
+-- and quest actions which only members of their guild may accept.
+
+CREATE TABLE GuildMember (
+    guild_id INTEGER NOT NULL REFERENCES Guild (id),
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    join_date INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, adventurer_id)
+) STRICT;
+CREATE INDEX GuildMember_adventurer_id ON GuildMember (adventurer_id);
+
+ALTER TABLE Quest ADD COLUMN members_only INTEGER NOT NULL DEFAULT 0;
+
+PRAGMA user_version = 14;
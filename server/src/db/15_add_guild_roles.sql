@@ -0,0 +1,8 @@
+-- Lets guilds have several leaders, and adventurers have other roles in a guild,
+-- by making sure each adventurer has each role in a guild at most once.
+
+DELETE FROM AdventurerRole
+    WHERE id NOT IN (SELECT MIN(id) FROM AdventurerRole GROUP BY guild_id, adventurer_id, assigned_role);
+CREATE UNIQUE INDEX AdventurerRole_assignment ON AdventurerRole (guild_id, adventurer_id, assigned_role);
+
+PRAGMA user_version = 15;
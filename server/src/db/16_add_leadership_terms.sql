@@ -0,0 +1,19 @@
+-- Keeps a history of who has led each guild, and who appointed them.
+-- Leaders at the time of this migration get an open term with no start date,
+-- since we don't know when they started.
+
+CREATE TABLE GuildLeadershipTerm (
+    id INTEGER PRIMARY KEY,
+    guild_id INTEGER NOT NULL REFERENCES Guild (id),
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    appointed_by INTEGER REFERENCES Adventurer (id),
+    start_date INTEGER,
+    end_date INTEGER,
+    end_reason TEXT
+) STRICT;
+CREATE INDEX GuildLeadershipTerm_guild_id ON GuildLeadershipTerm (guild_id);
+
+INSERT INTO GuildLeadershipTerm (guild_id, adventurer_id)
+    SELECT guild_id, adventurer_id FROM AdventurerRole WHERE assigned_role = 'leader' ORDER BY id;
+
+PRAGMA user_version = 16;
@@ -0,0 +1,5 @@
+-- Lets super users archive guilds, which hides them without losing their history.
+
+ALTER TABLE Guild ADD COLUMN archived_date INTEGER;
+
+PRAGMA user_version = 17;
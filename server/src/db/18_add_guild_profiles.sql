@@ -0,0 +1,10 @@
+-- Gives guilds a profile: a description, a mission statement, colours, and an icon,
+-- so the frontend doesn't have to hardcode how each guild looks.
+
+ALTER TABLE Guild ADD COLUMN description TEXT;
+ALTER TABLE Guild ADD COLUMN mission TEXT;
+ALTER TABLE Guild ADD COLUMN primary_colour TEXT;
+ALTER TABLE Guild ADD COLUMN accent_colour TEXT;
+ALTER TABLE Guild ADD COLUMN icon_blob_key TEXT;
+
+PRAGMA user_version = 18;
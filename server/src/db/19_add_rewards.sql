@@ -0,0 +1,26 @@
+-- Adds a catalogue of rewards, which adventurers can redeem with the XP they've earned.
+
+CREATE TABLE Reward (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    cost INTEGER NOT NULL,
+    stock INTEGER,
+    available INTEGER NOT NULL DEFAULT 1,
+    created_date INTEGER NOT NULL
+) STRICT;
+
+CREATE TABLE RewardRedemption (
+    id INTEGER PRIMARY KEY,
+    reward_id INTEGER NOT NULL REFERENCES Reward (id),
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    cost INTEGER NOT NULL,
+    redeemed_date INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    resolved_date INTEGER,
+    resolved_by INTEGER REFERENCES Adventurer (id)
+) STRICT;
+CREATE INDEX RewardRedemption_adventurer_id ON RewardRedemption (adventurer_id);
+CREATE INDEX RewardRedemption_status ON RewardRedemption (status);
+
+PRAGMA user_version = 19;
@@ -0,0 +1,17 @@
+-- Adds kudos, which adventurers send each other in recognition of their work,
+-- and lets the XP ledger record XP awarded along with kudos.
+
+CREATE TABLE Kudos (
+    id INTEGER PRIMARY KEY,
+    sender_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    recipient_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    message TEXT NOT NULL,
+    quest_id INTEGER REFERENCES Quest (id),
+    sent_date INTEGER NOT NULL
+) STRICT;
+CREATE INDEX Kudos_recipient_id ON Kudos (recipient_id);
+CREATE INDEX Kudos_sender_id ON Kudos (sender_id, sent_date);
+
+ALTER TABLE XpAward ADD COLUMN kudos_id INTEGER REFERENCES Kudos (id);
+
+PRAGMA user_version = 20;
@@ -0,0 +1,16 @@
+-- Adds threaded comments on guild quest actions.
+
+CREATE TABLE QuestComment (
+    id INTEGER PRIMARY KEY,
+    quest_id INTEGER NOT NULL REFERENCES Quest (id),
+    author_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    parent_id INTEGER REFERENCES QuestComment (id),
+    body TEXT NOT NULL,
+    created_date INTEGER NOT NULL,
+    edited_date INTEGER,
+    deleted_date INTEGER,
+    deleted_by INTEGER REFERENCES Adventurer (id)
+) STRICT;
+CREATE INDEX QuestComment_quest_id ON QuestComment (quest_id);
+
+PRAGMA user_version = 21;
@@ -0,0 +1,15 @@
+-- Gives adventurers a profile: pronouns, a job title, a department, a bio, and an avatar,
+-- each of which they can choose who gets to see.
+
+ALTER TABLE Adventurer ADD COLUMN pronouns TEXT;
+ALTER TABLE Adventurer ADD COLUMN job_title TEXT;
+ALTER TABLE Adventurer ADD COLUMN department TEXT;
+ALTER TABLE Adventurer ADD COLUMN bio TEXT;
+ALTER TABLE Adventurer ADD COLUMN avatar_blob_key TEXT;
+ALTER TABLE Adventurer ADD COLUMN pronouns_visibility TEXT NOT NULL DEFAULT 'everyone';
+ALTER TABLE Adventurer ADD COLUMN job_title_visibility TEXT NOT NULL DEFAULT 'everyone';
+ALTER TABLE Adventurer ADD COLUMN department_visibility TEXT NOT NULL DEFAULT 'everyone';
+ALTER TABLE Adventurer ADD COLUMN bio_visibility TEXT NOT NULL DEFAULT 'everyone';
+ALTER TABLE Adventurer ADD COLUMN avatar_visibility TEXT NOT NULL DEFAULT 'everyone';
+
+PRAGMA user_version = 22;
@@ -0,0 +1,23 @@
+-- Adds an inbox of notifications for each adventurer, and which kinds of notification they want.
+
+CREATE TABLE Notification (
+    id INTEGER PRIMARY KEY,
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    kind TEXT NOT NULL,
+    guild_id INTEGER REFERENCES Guild (id),
+    quest_id INTEGER REFERENCES Quest (id),
+    kudos_id INTEGER REFERENCES Kudos (id),
+    message TEXT NOT NULL,
+    created_date INTEGER NOT NULL,
+    read_date INTEGER
+) STRICT;
+CREATE INDEX Notification_adventurer_id ON Notification (adventurer_id, read_date);
+
+CREATE TABLE NotificationPreference (
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    kind TEXT NOT NULL,
+    enabled INTEGER NOT NULL,
+    PRIMARY KEY (adventurer_id, kind)
+) STRICT;
+
+PRAGMA user_version = 23;
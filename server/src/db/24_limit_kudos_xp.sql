@@ -0,0 +1,18 @@
+-- Only lets an adventurer send kudos for a quest to the same adventurer once,
+-- so they can't award them XP over and over for it.
+
+-- Kudos which were sent more than once for a quest keep their messages,
+-- but only the first of them keeps the quest, and its XP.
+DELETE FROM XpAward
+    WHERE kudos_id IN (SELECT id FROM Kudos
+                       WHERE quest_id IS NOT NULL
+                           AND id NOT IN (SELECT MIN(id) FROM Kudos WHERE quest_id IS NOT NULL
+                                          GROUP BY sender_id, recipient_id, quest_id));
+UPDATE Kudos SET quest_id = NULL
+    WHERE quest_id IS NOT NULL
+        AND id NOT IN (SELECT MIN(id) FROM Kudos WHERE quest_id IS NOT NULL
+                       GROUP BY sender_id, recipient_id, quest_id);
+
+CREATE UNIQUE INDEX Kudos_quest ON Kudos (sender_id, recipient_id, quest_id) WHERE quest_id IS NOT NULL;
+
+PRAGMA user_version = 24;
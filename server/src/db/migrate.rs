@@ -33,6 +33,28 @@ pub(crate) fn ensure_updated(db: &Connection) -> Result<(), rusqlite::Error> {
     const MIGRATIONS: &[(&str, &str, &str)] = &[
         ("add_repeatable_quests", "adding repeatable quests", include_str!("01_add_repeatable_quests.sql")),
         ("add_adventurer_notes", "adding adventurer notes", include_str!("02_add_adventurer_notes.sql")),
+        ("add_quest_task_attachments", "adding quest task attachments", include_str!("03_add_quest_task_attachments.sql")),
+        ("add_xp_ledger", "adding the XP ledger", include_str!("04_add_xp_ledger.sql")),
+        ("add_leaderboard_opt_out", "adding leaderboard opt out", include_str!("05_add_leaderboard_opt_out.sql")),
+        ("add_achievements", "adding achievements", include_str!("06_add_achievements.sql")),
+        ("add_repeat_limits", "adding repeat limits", include_str!("07_add_repeat_limits.sql")),
+        ("add_quest_deadlines", "adding quest deadlines", include_str!("08_add_quest_deadlines.sql")),
+        ("add_quest_prerequisites", "adding quest prerequisites", include_str!("09_add_quest_prerequisites.sql")),
+        ("add_campaigns", "adding campaigns", include_str!("10_add_campaigns.sql")),
+        ("add_quest_tags", "adding quest tags", include_str!("11_add_quest_tags.sql")),
+        ("add_full_text_search", "adding full text search", include_str!("12_add_full_text_search.sql")),
+        ("add_quest_templates", "adding quest templates", include_str!("13_add_quest_templates.sql")),
+        ("add_guild_membership", "adding guild membership", include_str!("14_add_guild_membership.sql")),
+        ("add_guild_roles", "adding guild roles", include_str!("15_add_guild_roles.sql")),
+        ("add_leadership_terms", "adding guild leadership terms", include_str!("16_add_leadership_terms.sql")),
+        ("add_guild_archival", "adding guild archival", include_str!("17_add_guild_archival.sql")),
+        ("add_guild_profiles", "adding guild profiles", include_str!("18_add_guild_profiles.sql")),
+        ("add_rewards", "adding rewards", include_str!("19_add_rewards.sql")),
+        ("add_kudos", "adding kudos", include_str!("20_add_kudos.sql")),
+        ("add_quest_comments", "adding quest comments", include_str!("21_add_quest_comments.sql")),
+        ("add_adventurer_profiles", "adding adventurer profiles", include_str!("22_add_adventurer_profiles.sql")),
+        ("add_notifications", "adding notifications", include_str!("23_add_notifications.sql")),
+        ("limit_kudos_xp", "limiting the XP awarded with kudos", include_str!("24_limit_kudos_xp.sql")),
     ];
     for (i, (name, description, src)) in MIGRATIONS.iter().enumerate() {
         let dest_version = (i + 1) as i64;
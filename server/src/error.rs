@@ -2,7 +2,7 @@
 //! This module is meant to define error types of global concern,
 //! and any helper methods we might need for dealing with them.
 
-use crate::{GuildId, QuestId, QuestTaskId, UserId};
+use crate::{AchievementId, AttachmentId, CampaignId, CommentId, GuildId, NotificationId, QuestId, QuestTaskId, QuestTemplateId, RedemptionId, RewardId, UserId};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use std::convert::Infallible;
@@ -44,6 +44,140 @@ pub(crate) enum Error<E = Infallible> {
         quest_id: QuestId,
         guild_id: GuildId,
     },
+    AttachmentNotFound {
+        id: Option<AttachmentId>,
+    },
+    AttachmentTooLarge {
+        max_size: usize,
+    },
+    UnsupportedAttachmentType {
+        content_type: String,
+    },
+    InvalidAttachmentName,
+    BlobStore(std::io::Error),
+    InvalidLevelThresholds {
+        msg: String,
+    },
+    AchievementNotFound {
+        id: Option<AchievementId>,
+    },
+    QuestNotAvailable {
+        quest_id: QuestId,
+        /// When the quest will next be available, formatted for humans,
+        /// or `None` if it won't ever be.
+        available_date: Option<String>,
+    },
+    CampaignNotFound {
+        id: Option<CampaignId>,
+    },
+    InvalidCampaign {
+        msg: String,
+    },
+    NotGuildMember {
+        user_id: UserId,
+        guild_id: GuildId,
+    },
+    RoleNotAssigned {
+        user_id: UserId,
+        guild_id: GuildId,
+        role: &'static str,
+    },
+    NotEligibleGuildLeader {
+        user_id: UserId,
+    },
+    GuildArchived {
+        id: GuildId,
+    },
+    GuildHasHistory {
+        id: GuildId,
+        history: Vec<&'static str>,
+    },
+    InvalidGuildProfile {
+        msg: String,
+    },
+    GuildIconNotFound {
+        id: GuildId,
+    },
+    ImageTooLarge {
+        max_size: usize,
+    },
+    InvalidImage {
+        msg: String,
+    },
+    InvalidProfile {
+        msg: String,
+    },
+    AvatarNotFound {
+        id: UserId,
+    },
+    RewardNotFound {
+        id: Option<RewardId>,
+    },
+    InvalidReward {
+        msg: String,
+    },
+    RewardUnavailable {
+        id: RewardId,
+    },
+    InsufficientPoints {
+        balance: i64,
+        cost: u32,
+    },
+    PointsAlreadySpent {
+        quest_id: QuestId,
+    },
+    RedemptionNotFound {
+        id: RedemptionId,
+    },
+    RedemptionNotPending {
+        id: RedemptionId,
+    },
+    InvalidKudos {
+        msg: String,
+    },
+    KudosLimitReached {
+        limit: u32,
+    },
+    KudosAlreadySent {
+        recipient_id: UserId,
+        quest_id: QuestId,
+    },
+    CommentNotFound {
+        id: CommentId,
+    },
+    NotificationNotFound {
+        id: NotificationId,
+    },
+    InvalidComment {
+        msg: String,
+    },
+    QuestTemplateNotFound {
+        id: Option<QuestTemplateId>,
+    },
+    InvalidCatalogue {
+        msg: String,
+    },
+    PrerequisitesNotMet {
+        quest_id: QuestId,
+        unmet: Vec<QuestId>,
+    },
+    PrerequisiteCycle {
+        quest_id: QuestId,
+    },
+    InvalidQuestSchedule,
+    InvalidTag {
+        tag: String,
+    },
+    TooManyTags {
+        max: usize,
+    },
+    InvalidCompletionDays {
+        max: u32,
+    },
+    InvalidRepeatLimits,
+    QuestAbandoned {
+        id: QuestId,
+    },
     AccountAlreadyExists,
     CannotComputePasswordHash,
     UnauthorizedLogin,
@@ -128,6 +262,237 @@ impl<E: IntoResponse> IntoResponse for Error<E> {
                 format!("quest {quest_id} does not belong to guild {guild_id}"),
             )
                 .into_response(),
+            Self::AttachmentNotFound { id } => {
+                if let Some(id) = id {
+                    (
+                        StatusCode::NOT_FOUND,
+                        format!("no attachment with id = {id} exists"),
+                    )
+                        .into_response()
+                } else {
+                    (StatusCode::NOT_FOUND, "specified attachment not found").into_response()
+                }
+            }
+            Self::AttachmentTooLarge { max_size } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("attachments may be at most {max_size} bytes"),
+            )
+                .into_response(),
+            Self::UnsupportedAttachmentType { content_type } => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("attachments of type {content_type} are not accepted, or the file does not match its type"),
+            )
+                .into_response(),
+            Self::InvalidAttachmentName => (
+                StatusCode::BAD_REQUEST,
+                "attachment file names must be 1 to 255 characters, without slashes or control characters",
+            )
+                .into_response(),
+            Self::BlobStore(e) => {
+                tracing::error!("blob store error: {e:?}");
+                (StatusCode::INTERNAL_SERVER_ERROR, "file storage access failed").into_response()
+            }
+            Self::InvalidLevelThresholds { msg } => {
+                (StatusCode::BAD_REQUEST, format!("invalid level thresholds: {msg}")).into_response()
+            }
+            Self::AchievementNotFound { id } => {
+                if let Some(id) = id {
+                    (
+                        StatusCode::NOT_FOUND,
+                        format!("no achievement with id = {id} exists"),
+                    )
+                        .into_response()
+                } else {
+                    (StatusCode::NOT_FOUND, "specified achievement not found").into_response()
+                }
+            }
+            Self::QuestNotAvailable { quest_id, available_date } => {
+                if let Some(available_date) = available_date {
+                    (
+                        StatusCode::CONFLICT,
+                        format!("quest {quest_id} cannot be accepted until {available_date}"),
+                    )
+                        .into_response()
+                } else {
+                    (
+                        StatusCode::CONFLICT,
+                        format!("quest {quest_id} can no longer be accepted"),
+                    )
+                        .into_response()
+                }
+            }
+            Self::CampaignNotFound { id } => {
+                if let Some(id) = id {
+                    (
+                        StatusCode::NOT_FOUND,
+                        format!("no campaign with id = {id} exists"),
+                    )
+                        .into_response()
+                } else {
+                    (StatusCode::NOT_FOUND, "specified campaign not found").into_response()
+                }
+            }
+            Self::InvalidCampaign { msg } => {
+                (StatusCode::BAD_REQUEST, format!("invalid campaign: {msg}")).into_response()
+            }
+            Self::NotGuildMember { user_id, guild_id } => {
+                (
+                    StatusCode::FORBIDDEN,
+                    format!("adventurer {user_id} is not a member of guild {guild_id}"),
+                )
+                    .into_response()
+            }
+            Self::RoleNotAssigned { user_id, guild_id, role } => {
+                (
+                    StatusCode::NOT_FOUND,
+                    format!("adventurer {user_id} is not a {role} of guild {guild_id}"),
+                )
+                    .into_response()
+            }
+            Self::NotEligibleGuildLeader { user_id } => {
+                (
+                    StatusCode::FORBIDDEN,
+                    format!("adventurer {user_id} is not allowed to be a guild leader"),
+                )
+                    .into_response()
+            }
+            Self::GuildArchived { id } => {
+                (StatusCode::CONFLICT, format!("guild {id} has been archived")).into_response()
+            }
+            Self::GuildHasHistory { id, history } => {
+                (
+                    StatusCode::CONFLICT,
+                    format!(
+                        "guild {id} can't be deleted without losing its {}; archive it instead",
+                        history.join(", "),
+                    ),
+                )
+                    .into_response()
+            }
+            Self::InvalidGuildProfile { msg } => {
+                (StatusCode::BAD_REQUEST, format!("invalid guild profile: {msg}")).into_response()
+            }
+            Self::GuildIconNotFound { id } => {
+                (StatusCode::NOT_FOUND, format!("guild {id} has no icon")).into_response()
+            }
+            Self::ImageTooLarge { max_size } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("images may be at most {max_size} bytes"),
+            )
+                .into_response(),
+            Self::InvalidImage { msg } => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, format!("invalid image: {msg}")).into_response()
+            }
+            Self::InvalidProfile { msg } => {
+                (StatusCode::BAD_REQUEST, format!("invalid profile: {msg}")).into_response()
+            }
+            Self::AvatarNotFound { id } => {
+                (StatusCode::NOT_FOUND, format!("adventurer {id} has no avatar")).into_response()
+            }
+            Self::RewardNotFound { id } => {
+                if let Some(id) = id {
+                    (
+                        StatusCode::NOT_FOUND,
+                        format!("no reward with id = {id} exists"),
+                    )
+                        .into_response()
+                } else {
+                    (StatusCode::NOT_FOUND, "specified reward not found").into_response()
+                }
+            }
+            Self::InvalidReward { msg } => {
+                (StatusCode::BAD_REQUEST, format!("invalid reward: {msg}")).into_response()
+            }
+            Self::RewardUnavailable { id } => {
+                (StatusCode::CONFLICT, format!("reward {id} is unavailable, or out of stock")).into_response()
+            }
+            Self::InsufficientPoints { balance, cost } => (
+                StatusCode::CONFLICT,
+                format!("this reward costs {cost} XP, but only {balance} XP is left to spend"),
+            )
+                .into_response(),
+            Self::PointsAlreadySpent { quest_id } => (
+                StatusCode::CONFLICT,
+                format!("the XP awarded for quest {quest_id} has already been spent on rewards"),
+            )
+                .into_response(),
+            Self::RedemptionNotFound { id } => {
+                (StatusCode::NOT_FOUND, format!("no redemption with id = {id} exists")).into_response()
+            }
+            Self::RedemptionNotPending { id } => {
+                (StatusCode::CONFLICT, format!("redemption {id} has already been fulfilled or cancelled")).into_response()
+            }
+            Self::InvalidKudos { msg } => {
+                (StatusCode::BAD_REQUEST, format!("invalid kudos: {msg}")).into_response()
+            }
+            Self::KudosLimitReached { limit } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("you may send at most {limit} kudos a day"),
+            )
+                .into_response(),
+            Self::KudosAlreadySent { recipient_id, quest_id } => (
+                StatusCode::CONFLICT,
+                format!("you've already sent adventurer {recipient_id} kudos for quest {quest_id}"),
+            )
+                .into_response(),
+            Self::CommentNotFound { id } => {
+                (StatusCode::NOT_FOUND, format!("no comment with id = {id} exists")).into_response()
+            }
+            Self::NotificationNotFound { id } => {
+                (StatusCode::NOT_FOUND, format!("no notification with id = {id} exists")).into_response()
+            }
+            Self::InvalidComment { msg } => {
+                (StatusCode::BAD_REQUEST, format!("invalid comment: {msg}")).into_response()
+            }
+            Self::QuestTemplateNotFound { id } => {
+                if let Some(id) = id {
+                    (
+                        StatusCode::NOT_FOUND,
+                        format!("no quest template with id = {id} exists"),
+                    )
+                        .into_response()
+                } else {
+                    (StatusCode::NOT_FOUND, "specified quest template not found").into_response()
+                }
+            }
+            Self::InvalidCatalogue { msg } => {
+                (StatusCode::BAD_REQUEST, format!("invalid catalogue: {msg}")).into_response()
+            }
+            Self::PrerequisitesNotMet { quest_id, unmet } => {
+                let unmet = unmet.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ");
+                (
+                    StatusCode::CONFLICT,
+                    format!("quest {quest_id} cannot be accepted until these quests are completed: {unmet}"),
+                )
+                    .into_response()
+            }
+            Self::PrerequisiteCycle { quest_id } => {
+                (StatusCode::BAD_REQUEST, format!("quest {quest_id} cannot be its own prerequisite, directly or indirectly")).into_response()
+            }
+            Self::InvalidTag { tag } => {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid tag {tag:?}: tags may only contain letters, digits, spaces, and dashes, and be at most 32 characters long"),
+                )
+                    .into_response()
+            }
+            Self::TooManyTags { max } => {
+                (StatusCode::BAD_REQUEST, format!("a quest action may have at most {max} tags")).into_response()
+            }
+            Self::InvalidQuestSchedule => {
+                (StatusCode::BAD_REQUEST, "a quest action's close date must be after its open date").into_response()
+            }
+            Self::InvalidCompletionDays { max } => (
+                StatusCode::BAD_REQUEST,
+                format!("a quest action must give adventurers between 1 and {max} days to complete it"),
+            )
+                .into_response(),
+            Self::InvalidRepeatLimits => {
+                (StatusCode::BAD_REQUEST, "a quest action's repeat limit and repeat period must be at least 1").into_response()
+            }
+            Self::QuestAbandoned { id } => {
+                (StatusCode::CONFLICT, format!("quest {id} was abandoned after its due date")).into_response()
+            }
             Self::AccountAlreadyExists => {
                 (StatusCode::BAD_REQUEST, "account already exists").into_response()
             }
@@ -26,6 +26,13 @@ VALUES
 (1, 2, 'leader'),
 (2, 5, 'leader');
 
+INSERT INTO GuildLeadershipTerm (guild_id, adventurer_id, appointed_by, start_date)
+VALUES
+(1, 1, NULL, unixepoch()),
+(6, 1, NULL, unixepoch()),
+(2, 1, NULL, unixepoch()),
+(5, 2, NULL, unixepoch());
+
 INSERT INTO Permission (adventurer_id, permission_type)
 VALUES
 (1, 0),
@@ -14,7 +14,7 @@
 -- A randomly generated number. This is hardcoded elsewhere,
 -- so grep for everywhere it's used in the server before changing it.
 PRAGMA application_id = 249251854;
-PRAGMA user_version = 2;
+PRAGMA user_version = 24;
 
 -- Unlike other journaling modes, WAL mode needs only to be set once,
 -- instead of upon each connection to the database.
@@ -23,9 +23,18 @@ PRAGMA journal_mode = WAL;
 -- Honestly, calling these character classes would still be more intuitive to me,
 -- since "guild" sounds like "organization" or "company" to me.
 -- Oh well.
+-- An archived guild is hidden from adventurers, and its quest actions can't be accepted,
+-- but everything which happened in it is kept.
+-- The colours are CSS hex colours, like '#1a2b3c', and the icon is a PNG in the blob store.
 CREATE TABLE Guild (
     id INTEGER PRIMARY KEY,
-    name TEXT NOT NULL
+    name TEXT NOT NULL,
+    archived_date INTEGER,
+    description TEXT,
+    mission TEXT,
+    primary_colour TEXT,
+    accent_colour TEXT,
+    icon_blob_key TEXT
 ) STRICT;
 -- We use strict tables, as described here:
 -- https://www.sqlite.org/stricttables.html
@@ -51,8 +60,65 @@ CREATE TABLE Quest (
     -- Available values:
     --  - Not Repeatable (0) (false)
     --  - Repeatable     (1) (true)
-    repeatable INTEGER NOT NULL DEFAULT 0
+    repeatable INTEGER NOT NULL DEFAULT 0,
+    -- The following only apply to repeatable quests, and are NULL when they don't apply.
+    -- The number of seconds an adventurer must wait after accepting this quest,
+    -- before they may accept it again.
+    repeat_cooldown INTEGER,
+    -- The most times an adventurer may accept this quest in each repeat_period,
+    -- or ever, if repeat_period is NULL.
+    repeat_limit INTEGER,
+    -- The length, in seconds, of the window repeat_limit applies to.
+    repeat_period INTEGER,
+    -- On guild quests, the number of days adventurers have to complete the quest
+    -- after accepting it, or NULL if there's no deadline.
+    completion_days INTEGER,
+    -- On accepted quests, when the quest is due, or NULL if there's no deadline.
+    due_date INTEGER,
+    -- Set on accepted quests which were left past their due date.
+    -- Unlike a cancelled quest, an abandoned quest isn't deleted.
+    abandoned_date INTEGER,
+    -- On guild quests, whether only members of the guild may accept the quest.
+    --  - Anyone       (0) (false)
+    --  - Members Only (1) (true)
+    members_only INTEGER NOT NULL DEFAULT 0
+) STRICT;
+CREATE INDEX Quest_close_date ON Quest (close_date);
+CREATE INDEX Quest_due_date ON Quest (due_date);
+
+-- An adventurer must have completed the prerequisite quests of a guild quest
+-- before they may accept it.
+CREATE TABLE QuestPrerequisite (
+    quest_id INTEGER NOT NULL REFERENCES Quest (id),
+    prerequisite_id INTEGER NOT NULL REFERENCES Quest (id),
+    PRIMARY KEY (quest_id, prerequisite_id)
+) STRICT;
+
+-- Tags let leaders sort guild quests into categories, like "accessibility" or "hiring".
+-- Tags are stored in lowercase.
+CREATE TABLE QuestTag (
+    quest_id INTEGER NOT NULL REFERENCES Quest (id),
+    tag TEXT NOT NULL,
+    PRIMARY KEY (quest_id, tag)
+) STRICT;
+CREATE INDEX QuestTag_tag ON QuestTag (tag);
+
+-- Comments adventurers leave on guild quest actions, to ask about them.
+-- Replies point at the comment they reply to with parent_id.
+-- Deleted comments are kept, without being shown, so the replies to them still make sense.
+CREATE TABLE QuestComment (
+    id INTEGER PRIMARY KEY,
+    quest_id INTEGER NOT NULL REFERENCES Quest (id),
+    author_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    parent_id INTEGER REFERENCES QuestComment (id),
+    body TEXT NOT NULL,
+    created_date INTEGER NOT NULL,
+    edited_date INTEGER,
+    deleted_date INTEGER,
+    -- The author, or the guild leader or super user who removed the comment.
+    deleted_by INTEGER REFERENCES Adventurer (id)
 ) STRICT;
+CREATE INDEX QuestComment_quest_id ON QuestComment (quest_id);
 
 CREATE TABLE QuestDetail (
     id INTEGER PRIMARY KEY,
@@ -73,6 +139,43 @@ CREATE TABLE QuestTask (
     xp INTEGER NOT NULL
 ) STRICT;
 
+-- Quest templates are quest actions which don't belong to any guild.
+-- Super users curate them, and guild leaders copy them into their guilds as new quest actions.
+-- The columns mean the same as they do on Quest and the tables hanging off of it.
+CREATE TABLE QuestTemplate (
+    id INTEGER PRIMARY KEY,
+    name TEXT,
+    repeatable INTEGER NOT NULL DEFAULT 0,
+    repeat_cooldown INTEGER,
+    repeat_limit INTEGER,
+    repeat_period INTEGER,
+    completion_days INTEGER,
+    created_date INTEGER NOT NULL,
+    deleted_date INTEGER
+) STRICT;
+
+CREATE TABLE QuestTemplateTask (
+    id INTEGER PRIMARY KEY,
+    template_id INTEGER NOT NULL REFERENCES QuestTemplate (id),
+    order_index INTEGER NOT NULL DEFAULT 0,
+    name TEXT NOT NULL,
+    description TEXT,
+    adventurer_note TEXT,
+    xp INTEGER NOT NULL
+) STRICT;
+
+CREATE TABLE QuestTemplateDetail (
+    id INTEGER PRIMARY KEY,
+    template_id INTEGER NOT NULL REFERENCES QuestTemplate (id),
+    description TEXT NOT NULL
+) STRICT;
+
+CREATE TABLE QuestTemplateTag (
+    template_id INTEGER NOT NULL REFERENCES QuestTemplate (id),
+    tag TEXT NOT NULL,
+    PRIMARY KEY (template_id, tag)
+) STRICT;
+
 CREATE TABLE Adventurer (
     id INTEGER PRIMARY KEY,
     name TEXT NOT NULL,
@@ -82,7 +185,26 @@ CREATE TABLE Adventurer (
     password_hash TEXT NOT NULL,
     -- randomly generated for each user, to make attacking many hashes at once
     -- more difficult. this is standard practice.
-    password_salt TEXT NOT NULL
+    password_salt TEXT NOT NULL,
+    -- Available values:
+    --  - Shown on leaderboards  (0) (false)
+    --  - Hidden on leaderboards (1) (true)
+    leaderboard_opt_out INTEGER NOT NULL DEFAULT 0,
+    pronouns TEXT,
+    job_title TEXT,
+    department TEXT,
+    bio TEXT,
+    avatar_blob_key TEXT,
+    -- Who may see each part of the adventurer's profile.
+    -- Available values:
+    --  - Everyone                           ('everyone')
+    --  - Only guild leaders and super users ('leaders')
+    --  - Only the adventurer themselves     ('only_me')
+    pronouns_visibility TEXT NOT NULL DEFAULT 'everyone',
+    job_title_visibility TEXT NOT NULL DEFAULT 'everyone',
+    department_visibility TEXT NOT NULL DEFAULT 'everyone',
+    bio_visibility TEXT NOT NULL DEFAULT 'everyone',
+    avatar_visibility TEXT NOT NULL DEFAULT 'everyone'
 ) STRICT;
 
 -- This table is not surfaced in the UI directly.
@@ -117,11 +239,278 @@ CREATE TABLE PartyMember (
     adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
     quest_id INTEGER NOT NULL REFERENCES Quest (id)
 ) STRICT;
+CREATE INDEX PartyMember_quest_id ON PartyMember (quest_id);
 
--- Note: Currently, the only Role is 'leader'.
+-- assigned_role currently has these accepted values (see GuildRole in src/main.rs):
+--  - 'leader'
+--  - 'officer'
+--  - 'reviewer'
+-- A guild may have any number of adventurers in each role.
 CREATE TABLE AdventurerRole (
     id INTEGER PRIMARY KEY,
     adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
     guild_id INTEGER NOT NULL REFERENCES Guild (id),
     assigned_role TEXT NOT NULL
 ) STRICT;
+CREATE UNIQUE INDEX AdventurerRole_assignment ON AdventurerRole (guild_id, adventurer_id, assigned_role);
+
+-- Each time an adventurer has been a leader of a guild.
+-- end_date is NULL while they're still a leader, and end_reason is one of:
+--  - 'removed'    (someone else took the role away from them)
+--  - 'ineligible' (they stopped being allowed to lead guilds)
+-- start_date is NULL for terms which began before we kept track of them.
+CREATE TABLE GuildLeadershipTerm (
+    id INTEGER PRIMARY KEY,
+    guild_id INTEGER NOT NULL REFERENCES Guild (id),
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    appointed_by INTEGER REFERENCES Adventurer (id),
+    start_date INTEGER,
+    end_date INTEGER,
+    end_reason TEXT
+) STRICT;
+CREATE INDEX GuildLeadershipTerm_guild_id ON GuildLeadershipTerm (guild_id);
+
+-- The adventurers who belong to each guild.
+-- Being a member of a guild has nothing to do with leading it.
+CREATE TABLE GuildMember (
+    guild_id INTEGER NOT NULL REFERENCES Guild (id),
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    join_date INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, adventurer_id)
+) STRICT;
+CREATE INDEX GuildMember_adventurer_id ON GuildMember (adventurer_id);
+
+-- Files which adventurers attach to their quest tasks,
+-- as evidence of how they completed them.
+CREATE TABLE QuestTaskAttachment (
+    id INTEGER PRIMARY KEY,
+    quest_task_id INTEGER NOT NULL REFERENCES QuestTask (id),
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    -- The file contents are kept in the blob store, under this key.
+    blob_key TEXT NOT NULL UNIQUE,
+    file_name TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    -- size is stored in bytes
+    size INTEGER NOT NULL,
+    upload_date INTEGER NOT NULL
+) STRICT;
+
+-- This is a ledger of all the XP adventurers have been awarded.
+-- We record XP here when a quest is completed, rather than adding up QuestTask.xp
+-- every time we need a total, so that totals stay cheap to compute.
+CREATE TABLE XpAward (
+    id INTEGER PRIMARY KEY,
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    guild_id INTEGER NOT NULL REFERENCES Guild (id),
+    -- The accepted quest this XP was awarded for completing.
+    quest_id INTEGER REFERENCES Quest (id),
+    -- The XP actually awarded, after any campaign's multiplier and bonus.
+    xp INTEGER NOT NULL,
+    award_date INTEGER NOT NULL,
+    -- The XP the quest was worth on its own.
+    base_xp INTEGER NOT NULL DEFAULT 0,
+    -- The campaign which boosted this award, if any.
+    campaign_id INTEGER REFERENCES Campaign (id),
+    -- The kudos this XP was awarded along with, if it wasn't for completing a quest.
+    kudos_id INTEGER REFERENCES Kudos (id),
+    UNIQUE(adventurer_id, quest_id)
+) STRICT;
+
+-- Recognition one adventurer sends another, which everyone can see.
+-- Kudos may be for a quest the recipient completed, in which case the recipient
+-- is also awarded a little XP in the quest's guild.
+CREATE TABLE Kudos (
+    id INTEGER PRIMARY KEY,
+    sender_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    recipient_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    message TEXT NOT NULL,
+    quest_id INTEGER REFERENCES Quest (id),
+    sent_date INTEGER NOT NULL
+) STRICT;
+CREATE INDEX Kudos_recipient_id ON Kudos (recipient_id);
+CREATE INDEX Kudos_sender_id ON Kudos (sender_id, sent_date);
+-- Kudos for a quest may only be sent to an adventurer once, so they only award XP once.
+CREATE UNIQUE INDEX Kudos_quest ON Kudos (sender_id, recipient_id, quest_id) WHERE quest_id IS NOT NULL;
+
+-- Things which happened that an adventurer ought to know about, like being made a guild leader.
+-- The message is written out when the notification is recorded, so it reads the same later on,
+-- even if the guild or quest it mentions is renamed.
+CREATE TABLE Notification (
+    id INTEGER PRIMARY KEY,
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    -- One of the kinds in notification::Kind, like 'quest_completed'.
+    kind TEXT NOT NULL,
+    guild_id INTEGER REFERENCES Guild (id),
+    quest_id INTEGER REFERENCES Quest (id),
+    kudos_id INTEGER REFERENCES Kudos (id),
+    message TEXT NOT NULL,
+    created_date INTEGER NOT NULL,
+    read_date INTEGER
+) STRICT;
+CREATE INDEX Notification_adventurer_id ON Notification (adventurer_id, read_date);
+
+-- Which kinds of notification adventurers have turned on or off.
+-- Every kind is on for adventurers who haven't said otherwise.
+CREATE TABLE NotificationPreference (
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    kind TEXT NOT NULL,
+    enabled INTEGER NOT NULL,
+    PRIMARY KEY (adventurer_id, kind)
+) STRICT;
+
+-- Rewards adventurers can spend their XP on, like swag or extra time off.
+-- A NULL stock means there's no limit to how many times a reward can be redeemed.
+CREATE TABLE Reward (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    cost INTEGER NOT NULL,
+    stock INTEGER,
+    available INTEGER NOT NULL DEFAULT 1,
+    created_date INTEGER NOT NULL
+) STRICT;
+
+-- Each time an adventurer has redeemed a reward. An adventurer's spendable balance is
+-- the XP they've been awarded, less the cost of every redemption which wasn't cancelled.
+-- status currently has these accepted values (see RedemptionStatus in src/main.rs):
+--  - 'pending'   (waiting for an administrator to hand the reward over)
+--  - 'fulfilled' (the adventurer has received the reward)
+--  - 'cancelled' (the reward won't be handed over, and its cost has been refunded)
+CREATE TABLE RewardRedemption (
+    id INTEGER PRIMARY KEY,
+    reward_id INTEGER NOT NULL REFERENCES Reward (id),
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    cost INTEGER NOT NULL,
+    redeemed_date INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    resolved_date INTEGER,
+    resolved_by INTEGER REFERENCES Adventurer (id)
+) STRICT;
+CREATE INDEX RewardRedemption_adventurer_id ON RewardRedemption (adventurer_id);
+CREATE INDEX RewardRedemption_status ON RewardRedemption (status);
+
+-- A campaign multiplies the XP awarded for quests completed between start_date and end_date,
+-- and adds xp_bonus on top, for the quest actions and guilds which participate in it.
+CREATE TABLE Campaign (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    start_date INTEGER NOT NULL,
+    end_date INTEGER NOT NULL,
+    xp_multiplier REAL NOT NULL DEFAULT 1.0,
+    xp_bonus INTEGER NOT NULL DEFAULT 0
+) STRICT;
+
+-- Guild quests which participate in a campaign.
+CREATE TABLE CampaignQuest (
+    campaign_id INTEGER NOT NULL REFERENCES Campaign (id),
+    quest_id INTEGER NOT NULL REFERENCES Quest (id),
+    PRIMARY KEY (campaign_id, quest_id)
+) STRICT;
+
+-- Guilds, all of whose quests participate in a campaign.
+CREATE TABLE CampaignGuild (
+    campaign_id INTEGER NOT NULL REFERENCES Campaign (id),
+    guild_id INTEGER NOT NULL REFERENCES Guild (id),
+    PRIMARY KEY (campaign_id, guild_id)
+) STRICT;
+
+-- Each row says that having xp_required XP in a guild puts an adventurer at a given level.
+-- Rows with a NULL guild_id are the default thresholds, which are used for guilds
+-- which haven't configured their own, and for adventurers' total XP across all guilds.
+CREATE TABLE LevelThreshold (
+    id INTEGER PRIMARY KEY,
+    guild_id INTEGER REFERENCES Guild (id),
+    level INTEGER NOT NULL,
+    xp_required INTEGER NOT NULL
+) STRICT;
+
+INSERT INTO LevelThreshold (guild_id, level, xp_required)
+VALUES
+(NULL, 1, 0),
+(NULL, 2, 100),
+(NULL, 3, 250),
+(NULL, 4, 500),
+(NULL, 5, 1000),
+(NULL, 6, 2000),
+(NULL, 7, 3500),
+(NULL, 8, 5000);
+
+-- Achievements are declared as data, and awarded automatically to adventurers
+-- who meet their criteria. See src/achievement.rs for how they're evaluated.
+-- criteria_type currently has these accepted values:
+--  - Complete Quest Actions             (0): complete at least `count` quest actions,
+--                                            in the guild `guild_id` if it's set
+--  - Complete Quest Action In Each Guild (1): complete a quest action in every guild
+--  - Complete Quest Action Worth XP     (2): complete a single quest action worth at least `xp` XP,
+--                                            in the guild `guild_id` if it's set
+--  - Earn XP                            (3): earn at least `xp` XP in total,
+--                                            in the guild `guild_id` if it's set
+CREATE TABLE Achievement (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    criteria_type INTEGER NOT NULL,
+    guild_id INTEGER REFERENCES Guild (id),
+    count INTEGER,
+    xp INTEGER
+) STRICT;
+
+CREATE TABLE AdventurerAchievement (
+    id INTEGER PRIMARY KEY,
+    adventurer_id INTEGER NOT NULL REFERENCES Adventurer (id),
+    achievement_id INTEGER NOT NULL REFERENCES Achievement (id),
+    award_date INTEGER NOT NULL,
+    UNIQUE(adventurer_id, achievement_id)
+) STRICT;
+
+-- Full-text search tables, which are kept in sync with the tables they index by triggers.
+-- Their rowids are the IDs of the rows they index.
+-- Accepted quests copy the names and descriptions of their tasks from their guild quest,
+-- so we only index those for guild quests. The notes adventurers write on accepted quests are indexed, though.
+CREATE VIRTUAL TABLE QuestTaskSearch USING fts5(name, description, adventurer_note);
+
+CREATE TRIGGER QuestTaskSearch_insert AFTER INSERT ON QuestTask BEGIN
+    INSERT INTO QuestTaskSearch (rowid, name, description, adventurer_note)
+        SELECT NEW.id,
+               iif(Quest.quest_type = 0, NEW.name, NULL),
+               iif(Quest.quest_type = 0, NEW.description, NULL),
+               NEW.adventurer_note
+        FROM Quest WHERE Quest.id = NEW.quest_id;
+END;
+CREATE TRIGGER QuestTaskSearch_update AFTER UPDATE OF name, description, adventurer_note ON QuestTask BEGIN
+    DELETE FROM QuestTaskSearch WHERE rowid = OLD.id;
+    INSERT INTO QuestTaskSearch (rowid, name, description, adventurer_note)
+        SELECT NEW.id,
+               iif(Quest.quest_type = 0, NEW.name, NULL),
+               iif(Quest.quest_type = 0, NEW.description, NULL),
+               NEW.adventurer_note
+        FROM Quest WHERE Quest.id = NEW.quest_id;
+END;
+CREATE TRIGGER QuestTaskSearch_delete AFTER DELETE ON QuestTask BEGIN
+    DELETE FROM QuestTaskSearch WHERE rowid = OLD.id;
+END;
+
+CREATE VIRTUAL TABLE QuestDetailSearch USING fts5(description);
+
+CREATE TRIGGER QuestDetailSearch_insert AFTER INSERT ON QuestDetail BEGIN
+    INSERT INTO QuestDetailSearch (rowid, description) VALUES (NEW.id, NEW.description);
+END;
+CREATE TRIGGER QuestDetailSearch_update AFTER UPDATE OF description ON QuestDetail BEGIN
+    UPDATE QuestDetailSearch SET description = NEW.description WHERE rowid = OLD.id;
+END;
+CREATE TRIGGER QuestDetailSearch_delete AFTER DELETE ON QuestDetail BEGIN
+    DELETE FROM QuestDetailSearch WHERE rowid = OLD.id;
+END;
+
+CREATE VIRTUAL TABLE GuildSearch USING fts5(name);
+
+CREATE TRIGGER GuildSearch_insert AFTER INSERT ON Guild BEGIN
+    INSERT INTO GuildSearch (rowid, name) VALUES (NEW.id, NEW.name);
+END;
+CREATE TRIGGER GuildSearch_update AFTER UPDATE OF name ON Guild BEGIN
+    UPDATE GuildSearch SET name = NEW.name WHERE rowid = OLD.id;
+END;
+CREATE TRIGGER GuildSearch_delete AFTER DELETE ON Guild BEGIN
+    DELETE FROM GuildSearch WHERE rowid = OLD.id;
+END;
@@ -0,0 +1,130 @@
+//! # Notifications
+//! Notifications tell adventurers about things which happened that concern them,
+//! like being awarded XP for a quest they completed, or being made a guild leader.
+//! The write handlers which make those things happen record notifications in the same transaction,
+//! so a change which gets rolled back never leaves a notification behind.
+//!
+//! Adventurers can turn each [kind](Kind) of notification off. Preferences are checked when a
+//! notification is recorded, so turning a kind off doesn't hide those already in someone's inbox.
+//!
+//! Quest actions added by importing a catalogue don't send notifications,
+//! so that moving a guild's quest actions in bulk doesn't flood its members' inboxes.
+
+use crate::{GuildId, KudosId, QuestId, UserId};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{named_params, Transaction};
+use serde::{Deserialize, Serialize};
+
+/// What a notification is about, as stored in `Notification.kind` and `NotificationPreference.kind`.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Kind {
+    /// A quest the adventurer was a party member of was completed, and they were awarded XP for it.
+    QuestCompleted,
+    /// The adventurer was made a leader of a guild.
+    MadeGuildLeader,
+    /// A quest action was published in a guild the adventurer is a member of.
+    NewQuestAction,
+    /// Another adventurer sent the adventurer kudos.
+    KudosReceived,
+}
+
+impl Kind {
+    pub(crate) const ALL: [Kind; 4] = [Kind::QuestCompleted, Kind::MadeGuildLeader, Kind::NewQuestAction, Kind::KudosReceived];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::QuestCompleted => "quest_completed",
+            Kind::MadeGuildLeader => "made_guild_leader",
+            Kind::NewQuestAction => "new_quest_action",
+            Kind::KudosReceived => "kudos_received",
+        }
+    }
+}
+impl rusqlite::ToSql for Kind {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+impl FromSql for Kind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let name = value.as_str()?;
+        Kind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == name)
+            .ok_or_else(|| FromSqlError::Other(format!("unknown notification kind {name:?}").into()))
+    }
+}
+
+// Each of these statements only notifies adventurers who haven't turned the kind of notification off,
+// checking NotificationPreference with NOT EXISTS, since every kind is on unless they've said otherwise.
+// Quest actions are named after their one task, so that's the name we use for them.
+
+/// Tell the party members of a quest which was just completed how much XP they were awarded.
+pub(crate) fn quest_completed(db: &Transaction, quest: QuestId) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO Notification (adventurer_id, kind, guild_id, quest_id, message, created_date)
+             SELECT PartyMember.adventurer_id, :kind, Quest.guild_id, Quest.id,
+                    'You completed \"' || QuestTask.name || '\" and earned ' || COALESCE(XpAward.xp, 0) || ' XP.',
+                    unixepoch()
+             FROM PartyMember
+                 INNER JOIN Quest ON Quest.id = PartyMember.quest_id
+                 INNER JOIN QuestTask ON QuestTask.id = (SELECT MIN(id) FROM QuestTask WHERE quest_id = Quest.id)
+                 LEFT JOIN XpAward
+                     ON XpAward.quest_id = Quest.id AND XpAward.adventurer_id = PartyMember.adventurer_id
+             WHERE PartyMember.quest_id = :quest_id
+                 AND NOT EXISTS (SELECT 0 FROM NotificationPreference
+                     WHERE adventurer_id = PartyMember.adventurer_id AND kind = :kind AND enabled = 0);",
+    )?;
+    query.execute(named_params! { ":kind": Kind::QuestCompleted, ":quest_id": quest })?;
+    Ok(())
+}
+
+/// Tell an adventurer they've been made a leader of a guild.
+pub(crate) fn made_guild_leader(db: &Transaction, user: UserId, guild: GuildId) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO Notification (adventurer_id, kind, guild_id, message, created_date)
+             SELECT :adventurer_id, :kind, id, 'You were made a leader of ' || name || '.', unixepoch()
+             FROM Guild
+             WHERE id = :guild_id
+                 AND NOT EXISTS (SELECT 0 FROM NotificationPreference
+                     WHERE adventurer_id = :adventurer_id AND kind = :kind AND enabled = 0);",
+    )?;
+    query.execute(named_params! { ":kind": Kind::MadeGuildLeader, ":adventurer_id": user, ":guild_id": guild })?;
+    Ok(())
+}
+
+/// Tell the members of a guild about a quest action which was just published in it.
+pub(crate) fn new_quest_action(db: &Transaction, quest: QuestId) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO Notification (adventurer_id, kind, guild_id, quest_id, message, created_date)
+             SELECT GuildMember.adventurer_id, :kind, Guild.id, Quest.id,
+                    'There''s a new quest action in ' || Guild.name || ': \"' || QuestTask.name || '\"',
+                    unixepoch()
+             FROM Quest
+                 INNER JOIN Guild ON Guild.id = Quest.guild_id
+                 INNER JOIN GuildMember ON GuildMember.guild_id = Guild.id
+                 INNER JOIN QuestTask ON QuestTask.id = (SELECT MIN(id) FROM QuestTask WHERE quest_id = Quest.id)
+             WHERE Quest.id = :quest_id
+                 AND NOT EXISTS (SELECT 0 FROM NotificationPreference
+                     WHERE adventurer_id = GuildMember.adventurer_id AND kind = :kind AND enabled = 0);",
+    )?;
+    query.execute(named_params! { ":kind": Kind::NewQuestAction, ":quest_id": quest })?;
+    Ok(())
+}
+
+/// Tell an adventurer someone sent them kudos.
+pub(crate) fn kudos_received(db: &Transaction, kudos: KudosId) -> Result<(), rusqlite::Error> {
+    let mut query = db.prepare_cached(
+        "INSERT INTO Notification (adventurer_id, kind, quest_id, kudos_id, message, created_date)
+             SELECT Kudos.recipient_id, :kind, Kudos.quest_id, Kudos.id,
+                    Sender.name || ' sent you kudos: \"' || Kudos.message || '\"', unixepoch()
+             FROM Kudos
+                 INNER JOIN Adventurer AS Sender ON Sender.id = Kudos.sender_id
+             WHERE Kudos.id = :kudos_id
+                 AND NOT EXISTS (SELECT 0 FROM NotificationPreference
+                     WHERE adventurer_id = Kudos.recipient_id AND kind = :kind AND enabled = 0);",
+    )?;
+    query.execute(named_params! { ":kind": Kind::KudosReceived, ":kudos_id": kudos })?;
+    Ok(())
+}